@@ -1,13 +1,15 @@
 use std::{cell::RefCell, rc::Rc};
 
-use crate::time::Jiffies;
+use crate::{global::TieKey, time::Jiffies};
 
 pub(crate) type SharedActor = Rc<RefCell<dyn SimulationActor>>;
 
 pub(crate) trait SimulationActor {
     fn start(&mut self);
-    fn step(&mut self);
-    fn peek_closest(&self) -> Option<Jiffies>;
+    // Returns false if no process handler ran, e.g. the event was only moved or deferred
+    fn step(&mut self) -> bool;
+    // Time and tie key of the next event, see global::order
+    fn peek_closest(&self) -> Option<(Jiffies, TieKey)>;
 }
 
 pub(crate) trait EventSubmitter {
@@ -1,6 +1,7 @@
-use crate::{MessagePtr, TimerId};
+use crate::{Jiffies, MessagePtr, TimerId};
 
 pub(crate) enum DScaleMessage {
-    NetworkMessage(MessagePtr),
+    NetworkMessage(MessagePtr, Jiffies), // Message and its send time
+
     Timer(TimerId),
 }
@@ -7,7 +7,7 @@
 
 use std::{any::Any, cmp::Reverse, collections::BinaryHeap, rc::Rc};
 
-use crate::{process::ProcessId, time::Jiffies};
+use crate::{global::TieKey, process::ProcessId, time::Jiffies};
 
 /// Core trait for all message types in DScale simulations.
 ///
@@ -196,6 +196,69 @@ pub trait Message: Any {
     fn virtual_size(&self) -> usize {
         usize::default()
     }
+
+    /// Returns the CPU time the receiver spends on this message before its handler runs.
+    ///
+    /// This is a per-message-type cost model: the cost is charged to the
+    /// destination process on every delivery, exactly as if its
+    /// [`ProcessHandle::on_message`] started with a call to [`consume_cpu`].
+    /// Use it for costs that depend only on the message, such as verifying
+    /// the signatures it carries.
+    ///
+    /// # Default Implementation
+    ///
+    /// The default implementation returns zero, so the message is handled instantly.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use dscale::{Jiffies, Message};
+    ///
+    /// struct Certificate {
+    ///     signatures: usize,
+    /// }
+    ///
+    /// impl Message for Certificate {
+    ///     fn cpu_cost(&self) -> Jiffies {
+    ///         Jiffies(self.signatures / 100) // Batch verification
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// [`ProcessHandle::on_message`]: crate::ProcessHandle::on_message
+    /// [`consume_cpu`]: crate::consume_cpu
+    fn cpu_cost(&self) -> Jiffies {
+        Jiffies::default()
+    }
+
+    /// Returns the name of the message type shown in traces and diagrams.
+    ///
+    /// The default implementation returns the full path of the implementing
+    /// type, as given by [`std::any::type_name`]. Override it to label messages
+    /// of one type differently, e.g. by protocol phase.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use dscale::Message;
+    ///
+    /// enum Phase {
+    ///     Prepare,
+    ///     Commit,
+    /// }
+    ///
+    /// impl Message for Phase {
+    ///     fn name(&self) -> &'static str {
+    ///         match self {
+    ///             Phase::Prepare => "Prepare",
+    ///             Phase::Commit => "Commit",
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
 }
 
 /// A smart pointer for type-safe message handling in DScale simulations.
@@ -324,10 +387,7 @@ impl MessagePtr {
     /// }
     /// ```
     pub fn try_as<T: 'static>(&self) -> Option<Rc<T>> {
-        match (self.0.clone() as Rc<dyn Any>).downcast::<T>() {
-            Err(_) => None,
-            Ok(m) => Some(m),
-        }
+        (self.0.clone() as Rc<dyn Any>).downcast::<T>().ok()
     }
 
     /// Checks if the message is of a specific type without extracting it.
@@ -424,43 +484,39 @@ pub struct ProcessStep {
     pub(crate) source: ProcessId,
     pub(crate) dest: ProcessId,
     pub(crate) message: Rc<dyn Message>,
+    pub(crate) sent_at: Jiffies,
 }
 
 #[derive(Clone)]
 pub struct RoutedMessage {
     pub(crate) arrival_time: Jiffies,
+    pub(crate) tie: TieKey,
     pub(crate) step: ProcessStep,
 }
 
+impl RoutedMessage {
+    pub(crate) fn order(&self) -> (Jiffies, TieKey) {
+        (self.arrival_time, self.tie)
+    }
+}
+
 impl PartialEq for RoutedMessage {
     fn eq(&self, other: &Self) -> bool {
-        self.arrival_time.eq(&other.arrival_time)
+        self.order().eq(&other.order())
     }
 }
 
 impl Eq for RoutedMessage {}
 
 impl PartialOrd for RoutedMessage {
-    fn ge(&self, other: &Self) -> bool {
-        self.arrival_time.ge(&other.arrival_time)
-    }
-    fn le(&self, other: &Self) -> bool {
-        self.arrival_time.le(&other.arrival_time)
-    }
-    fn gt(&self, other: &Self) -> bool {
-        self.arrival_time.gt(&other.arrival_time)
-    }
-    fn lt(&self, other: &Self) -> bool {
-        self.arrival_time.lt(&other.arrival_time)
-    }
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.arrival_time.partial_cmp(&other.arrival_time)
+        Some(self.cmp(other))
     }
 }
 
 impl Ord for RoutedMessage {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.arrival_time.cmp(&other.arrival_time)
+        self.order().cmp(&other.order())
     }
 }
 
@@ -0,0 +1,397 @@
+//! Systematic state-space exploration for small configurations.
+//!
+//! Instead of sampling latencies, the explorer takes control over the order in
+//! which concurrently enabled events happen: every in-flight message and every
+//! pending timer is a candidate transition, and all orders are enumerated up to
+//! a bounded depth. Invariants are checked on every reached state.
+//!
+//! Exploration is stateless: each branch is re-executed from scratch by
+//! replaying its prefix, so process types need no `Clone`. Redundant orders are
+//! pruned with sleep-set partial-order reduction, where two transitions are
+//! independent if they run on different processes.
+//!
+//! While under control, the network ignores latency and bandwidth settings and
+//! the clock advances by one jiffy per transition, so timers may fire before
+//! their deadline. Handlers that talk through side channels (such as [`anykv`]
+//! or [`global_unique_id`]) are assumed to commute across processes.
+//!
+//! # Time-dependent handlers
+//!
+//! Because every transition moves the clock, two transitions on different
+//! processes do not commute if either handler depends on the time it runs
+//! at: it reads [`now`] or [`local_now`], or schedules a timer (its deadline
+//! is relative to now). Sleep sets then prune orders that lead to different
+//! states, and violations can be missed. Explore such protocols with
+//! [`Explorer::partial_order_reduction`] turned off, at the cost of
+//! enumerating every order.
+//!
+//! [`anykv`]: crate::global::anykv
+//! [`global_unique_id`]: crate::global_unique_id
+//! [`now`]: crate::now
+//! [`local_now`]: crate::local_now
+
+use std::{
+    panic::{self, AssertUnwindSafe},
+    time::{Duration, Instant},
+};
+
+use log::info;
+
+use crate::{
+    ProcessId, Simulation, TimerId,
+    fuzz::{PanicCapture, take_last_panic},
+};
+
+/// A single step of a controlled execution.
+///
+/// Messages are identified by their ordinal on the `(from, to)` link, which is
+/// stable across replays of the same prefix.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Transition {
+    Deliver {
+        from: ProcessId,
+        to: ProcessId,
+        seq: usize,
+    },
+    Timer {
+        process: ProcessId,
+        id: TimerId,
+    },
+}
+
+impl Transition {
+    /// Process whose handler executes this transition.
+    pub fn process(&self) -> ProcessId {
+        match *self {
+            Transition::Deliver { to, .. } => to,
+            Transition::Timer { process, .. } => process,
+        }
+    }
+
+    fn independent(&self, other: &Transition) -> bool {
+        self.process() != other.process()
+    }
+}
+
+/// What went wrong in a counterexample.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ViolationKind {
+    /// The named invariant returned `false`.
+    Invariant(String),
+    /// A handler panicked.
+    Panic(String),
+}
+
+/// A schedule leading from the initial state to a bad one.
+#[derive(Clone, Debug)]
+pub struct Violation {
+    pub trace: Vec<Transition>,
+    pub kind: ViolationKind,
+}
+
+/// Statistics of a finished exploration.
+#[derive(Debug, Default)]
+pub struct ExplorationReport {
+    /// Executions (maximal paths) started.
+    pub executions: usize,
+    /// Transitions fired, not counting prefix replays.
+    pub transitions: usize,
+    /// Executions that ended with nothing left to run.
+    pub quiescent: usize,
+    /// Executions cut by the depth bound.
+    pub depth_bounded: usize,
+    /// Executions pruned because all enabled transitions were asleep.
+    pub pruned: usize,
+    /// Whether the bounded state space was exhausted.
+    pub complete: bool,
+    pub elapsed: Duration,
+    pub violation: Option<Violation>,
+}
+
+type Factory = Box<dyn Fn() -> Simulation>;
+type Invariant = (String, Box<dyn Fn() -> bool>);
+
+struct Node {
+    enabled: Vec<Transition>,
+    sleep: Vec<Transition>,
+    chosen: Transition,
+}
+
+/// Bounded model checker over delivery orders.
+///
+/// # Examples
+///
+/// ```rust
+/// use dscale::explore::{Explorer, Transition, ViolationKind};
+/// use dscale::global::anykv;
+/// use dscale::*;
+///
+/// struct Write;
+/// impl Message for Write {}
+///
+/// #[derive(Default)]
+/// struct Node;
+///
+/// impl ProcessHandle for Node {
+///     fn start(&mut self) {
+///         if rank() != 3 {
+///             send_to(3, Write);
+///         }
+///     }
+///     fn on_message(&mut self, from: ProcessId, _message: MessagePtr) {
+///         anykv::modify::<Option<ProcessId>>("first", |first| {
+///             first.get_or_insert(from);
+///         });
+///     }
+///     fn on_timer(&mut self, _id: TimerId) {}
+/// }
+///
+/// let report = Explorer::new(|| {
+///     let sim = SimulationBuilder::default()
+///         .add_pool::<Node>("nodes", 3)
+///         .build();
+///     anykv::set::<Option<ProcessId>>("first", None);
+///     sim
+/// })
+/// .invariant("P1 wins the race", || {
+///     anykv::get::<Option<ProcessId>>("first") != Some(2)
+/// })
+/// .run();
+///
+/// let violation = report.violation.expect("race should be found");
+/// assert_eq!(violation.kind, ViolationKind::Invariant("P1 wins the race".to_string()));
+/// assert_eq!(violation.trace, vec![Transition::Deliver { from: 2, to: 3, seq: 0 }]);
+/// ```
+pub struct Explorer {
+    factory: Factory,
+    invariants: Vec<Invariant>,
+    max_depth: usize,
+    max_executions: usize,
+    wall_clock_budget: Option<Duration>,
+    partial_order_reduction: bool,
+}
+
+impl Explorer {
+    /// Creates an explorer around a scenario factory.
+    ///
+    /// The factory is called once per execution and must build a fresh
+    /// simulation; latency, bandwidth and time budget settings are ignored.
+    pub fn new(factory: impl Fn() -> Simulation + 'static) -> Self {
+        Self {
+            factory: Box::new(factory),
+            invariants: Vec::new(),
+            max_depth: 64,
+            max_executions: usize::MAX,
+            wall_clock_budget: None,
+            partial_order_reduction: true,
+        }
+    }
+
+    /// Adds an invariant checked after start and after every transition.
+    pub fn invariant(mut self, name: &str, invariant: impl Fn() -> bool + 'static) -> Self {
+        self.invariants
+            .push((name.to_string(), Box::new(invariant)));
+        self
+    }
+
+    /// Maximum number of transitions in a single execution.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Stops after this many executions.
+    pub fn max_executions(mut self, max_executions: usize) -> Self {
+        self.max_executions = max_executions;
+        self
+    }
+
+    /// Stops once this much wall-clock time has passed.
+    pub fn wall_clock_budget(mut self, budget: Duration) -> Self {
+        self.wall_clock_budget = Some(budget);
+        self
+    }
+
+    /// Enables or disables sleep-set reduction (enabled by default).
+    ///
+    /// The reduction is only sound for handlers that do not depend on the
+    /// time they run at; turn it off for protocols whose handlers read the
+    /// clock or schedule timers, see
+    /// [time-dependent handlers](self#time-dependent-handlers).
+    pub fn partial_order_reduction(mut self, enabled: bool) -> Self {
+        self.partial_order_reduction = enabled;
+        self
+    }
+
+    /// Explores the bounded state space until a violation is found or it is exhausted.
+    pub fn run(&self) -> ExplorationReport {
+        let _guard = PanicCapture::install();
+        let started = Instant::now();
+        let mut report = ExplorationReport::default();
+        let mut stack: Vec<Node> = Vec::new();
+
+        loop {
+            if report.executions >= self.max_executions
+                || self
+                    .wall_clock_budget
+                    .is_some_and(|budget| started.elapsed() >= budget)
+            {
+                break;
+            }
+
+            report.executions += 1;
+            if let Some(violation) = self.execute(&mut stack, &mut report) {
+                info!(
+                    "Violation after {} transitions: {:?}",
+                    violation.trace.len(),
+                    violation.kind
+                );
+                report.violation = Some(violation);
+                break;
+            }
+
+            if !Self::backtrack(&mut stack) {
+                report.complete = true;
+                break;
+            }
+        }
+
+        report.elapsed = started.elapsed();
+        info!(
+            "Exploration done: {} executions, {} transitions, complete: {}",
+            report.executions, report.transitions, report.complete
+        );
+        report
+    }
+
+    /// Re-executes a schedule, checking invariants along the way.
+    pub fn replay(&self, trace: &[Transition]) -> Option<Violation> {
+        let _guard = PanicCapture::install();
+        take_last_panic();
+
+        let mut fired = Vec::new();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let mut simulation = (self.factory)();
+            simulation.start_controlled();
+            if let Some(name) = self.violated_invariant() {
+                return Some(name);
+            }
+            for transition in trace {
+                fired.push(*transition);
+                simulation.fire(*transition);
+                if let Some(name) = self.violated_invariant() {
+                    return Some(name);
+                }
+            }
+            None
+        }));
+
+        Self::verdict(result, fired)
+    }
+}
+
+impl Explorer {
+    // Replays the chosen prefix, then extends it greedily until quiescence,
+    // the depth bound or a violation.
+    fn execute(&self, stack: &mut Vec<Node>, report: &mut ExplorationReport) -> Option<Violation> {
+        take_last_panic();
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let mut simulation = (self.factory)();
+            simulation.start_controlled();
+
+            if stack.is_empty()
+                && let Some(name) = self.violated_invariant()
+            {
+                return Some(name);
+            }
+
+            // Only the last prefix transition (the one just switched to) reaches a new state
+            if let Some((last, prefix)) = stack.split_last() {
+                prefix.iter().for_each(|node| simulation.fire(node.chosen));
+                simulation.fire(last.chosen);
+                report.transitions += 1;
+                if let Some(name) = self.violated_invariant() {
+                    return Some(name);
+                }
+            }
+
+            loop {
+                if stack.len() >= self.max_depth {
+                    report.depth_bounded += 1;
+                    return None;
+                }
+
+                let enabled = simulation.enabled_transitions();
+                let sleep = match stack.last() {
+                    Some(parent) if self.partial_order_reduction => parent
+                        .sleep
+                        .iter()
+                        .filter(|asleep| asleep.independent(&parent.chosen))
+                        .copied()
+                        .collect(),
+                    _ => Vec::new(),
+                };
+
+                let Some(chosen) = enabled.iter().find(|t| !sleep.contains(t)).copied() else {
+                    if enabled.is_empty() {
+                        report.quiescent += 1;
+                    } else {
+                        report.pruned += 1;
+                    }
+                    return None;
+                };
+
+                stack.push(Node {
+                    enabled,
+                    sleep,
+                    chosen,
+                });
+                simulation.fire(chosen);
+                report.transitions += 1;
+
+                if let Some(name) = self.violated_invariant() {
+                    return Some(name);
+                }
+            }
+        }));
+
+        Self::verdict(result, stack.iter().map(|node| node.chosen).collect())
+    }
+
+    // Moves the deepest node with an unexplored sibling to that sibling.
+    // Explored transitions stay in the node's sleep set.
+    fn backtrack(stack: &mut Vec<Node>) -> bool {
+        while let Some(node) = stack.last_mut() {
+            node.sleep.push(node.chosen);
+            if let Some(next) = node.enabled.iter().find(|t| !node.sleep.contains(t)) {
+                node.chosen = *next;
+                return true;
+            }
+            stack.pop();
+        }
+        false
+    }
+
+    fn violated_invariant(&self) -> Option<String> {
+        self.invariants
+            .iter()
+            .find(|(_, invariant)| !invariant())
+            .map(|(name, _)| name.clone())
+    }
+
+    fn verdict(
+        result: std::thread::Result<Option<String>>,
+        trace: Vec<Transition>,
+    ) -> Option<Violation> {
+        let kind = match result {
+            Ok(violated) => ViolationKind::Invariant(violated?),
+            Err(_) => ViolationKind::Panic(
+                take_last_panic()
+                    .map(|(message, _)| message)
+                    .unwrap_or_else(|| "<unknown panic>".to_string()),
+            ),
+        };
+        Some(Violation { trace, kind })
+    }
+}
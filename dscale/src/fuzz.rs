@@ -0,0 +1,462 @@
+//! Seed fuzzing campaigns with failure minimization.
+//!
+//! This module drives a scenario through many seeds, records every seed that
+//! ends in a panic, a deadlock or a failed checker, and then shrinks each
+//! failing case (process count, time budget and injected faults) into the
+//! smallest configuration that still fails with the same seed.
+//!
+//! Every case is fully described by a [`FuzzCase`], so a minimized failure can
+//! be replayed deterministically with [`FuzzCampaign::replay`].
+
+use std::{
+    cell::RefCell,
+    fmt::Debug,
+    ops::Range,
+    panic::{self, AssertUnwindSafe},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use log::{info, warn};
+
+use crate::{Simulation, global, random::Seed, simulation::RunOutcome, time::Jiffies};
+
+/// A single, reproducible fuzzing input.
+///
+/// The scenario factory passed to [`FuzzCampaign::new`] receives a case and
+/// is expected to build a simulation out of it: use `seed` for
+/// [`SimulationBuilder::seed`], `time_budget` for
+/// [`SimulationBuilder::time_budget`], size the pools after `processes` and
+/// apply `faults` in whatever way the scenario understands them.
+///
+/// [`SimulationBuilder::seed`]: crate::SimulationBuilder::seed
+/// [`SimulationBuilder::time_budget`]: crate::SimulationBuilder::time_budget
+#[derive(Clone, Debug)]
+pub struct FuzzCase<F = ()> {
+    pub seed: Seed,
+    pub processes: usize,
+    pub time_budget: Jiffies,
+    pub faults: Vec<F>,
+}
+
+/// Why a fuzzing case was considered failed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FailureKind {
+    /// A process handler, an invariant assertion or the scenario itself panicked.
+    Panic(String),
+    /// No events were left before the time budget was exhausted.
+    Deadlock,
+    /// The run finished but the checker rejected its outcome.
+    CheckFailed,
+}
+
+/// A failing case together with the way it failed.
+#[derive(Clone, Debug)]
+pub struct FuzzFailure<F = ()> {
+    pub case: FuzzCase<F>,
+    pub kind: FailureKind,
+    /// Simulation time at which the failure was observed.
+    pub at: Jiffies,
+}
+
+/// Summary of a finished campaign.
+pub struct FuzzReport<F = ()> {
+    /// Number of seeds executed (minimization runs are not counted).
+    pub runs: usize,
+    /// Wall-clock time spent, including minimization.
+    pub elapsed: Duration,
+    /// Failures in the order their seeds were found, minimized if enabled.
+    pub failures: Vec<FuzzFailure<F>>,
+}
+
+impl<F> FuzzReport<F> {
+    pub fn failing_seeds(&self) -> Vec<Seed> {
+        self.failures.iter().map(|f| f.case.seed).collect()
+    }
+}
+
+type Factory<F> = Box<dyn Fn(&FuzzCase<F>) -> Simulation>;
+type Checker = Box<dyn Fn() -> bool>;
+type FaultSchedule<F> = Box<dyn Fn(Seed) -> Vec<F>>;
+
+/// Driver that runs a scenario over a range of seeds.
+///
+/// # Panics and `panic = "abort"`
+///
+/// Panics are caught with [`std::panic::catch_unwind`], so the campaign must
+/// be built with unwinding panics (the default for `dev` and `test`
+/// profiles). With `panic = "abort"` the first panicking seed terminates the
+/// host process.
+///
+/// # Examples
+///
+/// ```rust
+/// use dscale::fuzz::{FailureKind, FuzzCampaign, FuzzCase};
+/// use dscale::*;
+///
+/// #[derive(Default)]
+/// struct Fragile;
+///
+/// impl ProcessHandle for Fragile {
+///     fn start(&mut self) {
+///         schedule_timer_after(Jiffies(10));
+///     }
+///     fn on_message(&mut self, _from: ProcessId, _message: MessagePtr) {}
+///     fn on_timer(&mut self, _id: TimerId) {
+///         // Bug that shows up only with 3+ processes and late enough in time
+///         assert!(!(global::configuration::process_number() >= 3 && now() > Jiffies(500)));
+///         schedule_timer_after(Jiffies(10));
+///     }
+/// }
+///
+/// let report = FuzzCampaign::new(|case: &FuzzCase| {
+///     SimulationBuilder::default()
+///         .add_pool::<Fragile>("nodes", case.processes)
+///         .seed(case.seed)
+///         .time_budget(case.time_budget)
+///         .build()
+/// })
+/// .seeds(0..3)
+/// .processes(8)
+/// .time_budget(Jiffies(10_000))
+/// .run();
+///
+/// assert_eq!(report.failing_seeds(), vec![0, 1, 2]);
+/// let minimized = &report.failures[0];
+/// assert!(matches!(minimized.kind, FailureKind::Panic(_)));
+/// assert_eq!(minimized.case.processes, 3);
+/// assert!(minimized.case.time_budget <= Jiffies(1_000));
+/// ```
+pub struct FuzzCampaign<F = ()> {
+    factory: Factory<F>,
+    checker: Option<Checker>,
+    fault_schedule: Option<FaultSchedule<F>>,
+    seeds: Range<Seed>,
+    wall_clock_budget: Option<Duration>,
+    processes: usize,
+    min_processes: usize,
+    time_budget: Jiffies,
+    max_failures: usize,
+    minimize: bool,
+    shrink_attempts: usize,
+}
+
+impl<F: Clone + Debug> FuzzCampaign<F> {
+    /// Creates a campaign around a scenario factory.
+    ///
+    /// The factory is called once per executed case and must build a fresh
+    /// simulation. Global values the checker depends on (for example via
+    /// [`anykv`]) should be initialized inside the factory after `build()`.
+    ///
+    /// [`anykv`]: crate::global::anykv
+    pub fn new(factory: impl Fn(&FuzzCase<F>) -> Simulation + 'static) -> Self {
+        Self {
+            factory: Box::new(factory),
+            checker: None,
+            fault_schedule: None,
+            seeds: 0..1000,
+            wall_clock_budget: None,
+            processes: 3,
+            min_processes: 1,
+            time_budget: Jiffies(1_000_000),
+            max_failures: usize::MAX,
+            minimize: true,
+            shrink_attempts: 200,
+        }
+    }
+
+    /// Sets the range of seeds to run.
+    pub fn seeds(mut self, seeds: Range<Seed>) -> Self {
+        self.seeds = seeds;
+        self
+    }
+
+    /// Stops launching new seeds once this much wall-clock time has passed.
+    pub fn wall_clock_budget(mut self, budget: Duration) -> Self {
+        self.wall_clock_budget = Some(budget);
+        self
+    }
+
+    /// Sets the process count handed to the factory before shrinking.
+    pub fn processes(mut self, processes: usize) -> Self {
+        self.processes = processes;
+        self
+    }
+
+    /// Sets the smallest process count minimization may try.
+    pub fn min_processes(mut self, min_processes: usize) -> Self {
+        self.min_processes = min_processes;
+        self
+    }
+
+    /// Sets the simulation time budget handed to the factory before shrinking.
+    pub fn time_budget(mut self, time_budget: Jiffies) -> Self {
+        self.time_budget = time_budget;
+        self
+    }
+
+    /// Post-run checker, such as a linearizability check over recorded history.
+    ///
+    /// It is called after the time budget is exhausted, while the simulation
+    /// is still alive, so [`anykv`] values are still readable.
+    ///
+    /// [`anykv`]: crate::global::anykv
+    pub fn check(mut self, checker: impl Fn() -> bool + 'static) -> Self {
+        self.checker = Some(Box::new(checker));
+        self
+    }
+
+    /// Generates a randomized fault schedule for every seed.
+    ///
+    /// The generator receives the case seed, so the same seed always yields
+    /// the same schedule. Minimization may later drop individual faults.
+    pub fn fault_schedule(mut self, schedule: impl Fn(Seed) -> Vec<F> + 'static) -> Self {
+        self.fault_schedule = Some(Box::new(schedule));
+        self
+    }
+
+    /// Stops the campaign after this many failing seeds.
+    pub fn max_failures(mut self, max_failures: usize) -> Self {
+        self.max_failures = max_failures;
+        self
+    }
+
+    /// Enables or disables shrinking of failing cases (enabled by default).
+    pub fn minimize(mut self, minimize: bool) -> Self {
+        self.minimize = minimize;
+        self
+    }
+
+    /// Upper bound on extra runs spent shrinking a single failure.
+    pub fn shrink_attempts(mut self, shrink_attempts: usize) -> Self {
+        self.shrink_attempts = shrink_attempts;
+        self
+    }
+
+    /// Runs the campaign.
+    pub fn run(&self) -> FuzzReport<F> {
+        if cfg!(panic = "abort") {
+            warn!("Fuzzing with panic = \"abort\": a panicking seed will terminate the process");
+        }
+
+        let _guard = PanicCapture::install();
+        let started = Instant::now();
+        let mut runs = 0;
+        let mut failures = Vec::new();
+
+        for seed in self.seeds.clone() {
+            if self
+                .wall_clock_budget
+                .is_some_and(|budget| started.elapsed() >= budget)
+            {
+                info!("Wall-clock budget exhausted after {runs} seeds");
+                break;
+            }
+
+            let case = self.case_for(seed);
+            runs += 1;
+
+            if let Some(failure) = self.execute(&case) {
+                info!("Seed {seed} failed: {:?} at {}", failure.kind, failure.at);
+                let failure = if self.minimize {
+                    self.shrink(failure)
+                } else {
+                    failure
+                };
+                failures.push(failure);
+                if failures.len() >= self.max_failures {
+                    break;
+                }
+            }
+        }
+
+        info!("Fuzzing done: {runs} seeds, {} failures", failures.len());
+
+        FuzzReport {
+            runs,
+            elapsed: started.elapsed(),
+            failures,
+        }
+    }
+
+    /// Re-executes a single case, returning the failure it produces, if any.
+    pub fn replay(&self, case: &FuzzCase<F>) -> Option<FuzzFailure<F>> {
+        let _guard = PanicCapture::install();
+        self.execute(case)
+    }
+}
+
+impl<F: Clone + Debug> FuzzCampaign<F> {
+    fn case_for(&self, seed: Seed) -> FuzzCase<F> {
+        FuzzCase {
+            seed,
+            processes: self.processes,
+            time_budget: self.time_budget,
+            faults: self
+                .fault_schedule
+                .as_ref()
+                .map(|schedule| schedule(seed))
+                .unwrap_or_default(),
+        }
+    }
+
+    fn execute(&self, case: &FuzzCase<F>) -> Option<FuzzFailure<F>> {
+        take_last_panic();
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let mut simulation = (self.factory)(case);
+            let outcome = simulation.run_to_outcome();
+            let at = global::now();
+            match outcome {
+                RunOutcome::Deadlock => Some((FailureKind::Deadlock, at)),
+                RunOutcome::Finished => match &self.checker {
+                    Some(checker) if !checker() => Some((FailureKind::CheckFailed, at)),
+                    _ => None,
+                },
+            }
+        }));
+
+        let (kind, at) = match result {
+            Ok(verdict) => verdict?,
+            Err(_) => {
+                let (message, at) = take_last_panic()
+                    .unwrap_or_else(|| ("<unknown panic>".to_string(), Jiffies(0)));
+                (FailureKind::Panic(message), at)
+            }
+        };
+
+        Some(FuzzFailure {
+            case: case.clone(),
+            kind,
+            at,
+        })
+    }
+
+    // Greedy shrinking: each accepted candidate must still fail with the same
+    // kind of failure, so the minimized case reproduces the original bug.
+    fn shrink(&self, mut best: FuzzFailure<F>) -> FuzzFailure<F> {
+        let mut attempts = 0;
+
+        let try_candidate =
+            |best: &mut FuzzFailure<F>, candidate: FuzzCase<F>, attempts: &mut usize| {
+                if *attempts >= self.shrink_attempts {
+                    return false;
+                }
+                *attempts += 1;
+                match self.execute(&candidate) {
+                    Some(failure) if same_kind(&failure.kind, &best.kind) => {
+                        *best = failure;
+                        true
+                    }
+                    _ => false,
+                }
+            };
+
+        // Time: panics and deadlocks tell exactly when they happened
+        if best.kind != FailureKind::CheckFailed && best.at + Jiffies(1) < best.case.time_budget {
+            let mut candidate = best.case.clone();
+            candidate.time_budget = best.at + Jiffies(1);
+            try_candidate(&mut best, candidate, &mut attempts);
+        }
+
+        // Processes: halve first, then decrement
+        while best.case.processes > self.min_processes {
+            let half = (best.case.processes / 2).max(self.min_processes);
+            let mut candidate = best.case.clone();
+            candidate.processes = half;
+            if half < best.case.processes - 1 && try_candidate(&mut best, candidate, &mut attempts)
+            {
+                continue;
+            }
+            let mut candidate = best.case.clone();
+            candidate.processes -= 1;
+            if !try_candidate(&mut best, candidate, &mut attempts) {
+                break;
+            }
+        }
+
+        // Faults: drop one at a time
+        let mut i = 0;
+        while i < best.case.faults.len() {
+            let mut candidate = best.case.clone();
+            candidate.faults.remove(i);
+            if !try_candidate(&mut best, candidate, &mut attempts) {
+                i += 1;
+            }
+        }
+
+        // Time budget: halve while the failure survives
+        while best.case.time_budget > Jiffies(1) {
+            let mut candidate = best.case.clone();
+            candidate.time_budget = Jiffies(best.case.time_budget.0 / 2);
+            if !try_candidate(&mut best, candidate, &mut attempts) {
+                break;
+            }
+        }
+
+        info!(
+            "Minimized seed {}: {} processes, {}, {} faults ({attempts} runs)",
+            best.case.seed,
+            best.case.processes,
+            best.case.time_budget,
+            best.case.faults.len()
+        );
+
+        best
+    }
+}
+
+fn same_kind(l: &FailureKind, r: &FailureKind) -> bool {
+    std::mem::discriminant(l) == std::mem::discriminant(r)
+}
+
+thread_local! {
+    static LAST_PANIC: RefCell<Option<(String, Jiffies)>> = const { RefCell::new(None) };
+    static CAPTURING: RefCell<bool> = const { RefCell::new(false) };
+}
+
+type PanicHook = Arc<dyn Fn(&panic::PanicHookInfo<'_>) + Send + Sync + 'static>;
+
+// Silences panic output of the fuzzing thread and records the message and
+// simulation time instead. Other threads keep the previous hook.
+pub(crate) struct PanicCapture {
+    previous: PanicHook,
+}
+
+impl PanicCapture {
+    pub(crate) fn install() -> Self {
+        let previous: PanicHook = Arc::from(panic::take_hook());
+        let forward = previous.clone();
+        panic::set_hook(Box::new(move |info| {
+            if !CAPTURING.with_borrow(|capturing| *capturing) {
+                return forward(info);
+            }
+            let payload = info
+                .payload()
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| info.payload().downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "<non-string panic payload>".to_string());
+            let message = match info.location() {
+                Some(location) => format!("{payload} ({location})"),
+                None => payload,
+            };
+            LAST_PANIC.set(Some((message, global::now())));
+        }));
+        CAPTURING.set(true);
+        Self { previous }
+    }
+}
+
+// Message and simulation time of the last panic caught on this thread
+pub(crate) fn take_last_panic() -> Option<(String, Jiffies)> {
+    LAST_PANIC.take()
+}
+
+impl Drop for PanicCapture {
+    fn drop(&mut self) {
+        CAPTURING.set(false);
+        let previous = self.previous.clone();
+        panic::set_hook(Box::new(move |info| previous(info)));
+    }
+}
@@ -3,6 +3,7 @@ use std::{cell::RefCell, rc::Rc};
 use crate::{
     Destination, Message, ProcessId,
     actor::EventSubmitter,
+    global::{cpu, skew},
     network::NetworkActor,
     random::Randomizer,
     time::{
@@ -14,7 +15,7 @@ use crate::{
 
 pub struct SimulationAccess {
     process_on_execution: ProcessId,
-    pub(crate) scheduled_messages: Vec<(ProcessId, Destination, Rc<dyn Message>)>,
+    pub(crate) scheduled_messages: Vec<(ProcessId, Destination, Rc<dyn Message>, Jiffies)>,
     pub(crate) scheduled_timers: Vec<(ProcessId, TimerId, Jiffies)>,
     topology: Rc<Topology>,
     random: Randomizer,
@@ -53,8 +54,7 @@ impl SimulationAccess {
     }
 
     fn choose_from_pool(&mut self, name: &str) -> ProcessId {
-        self.random
-            .choose_from_slice(&self.topology.list_pool(name))
+        self.random.choose_from_slice(self.topology.list_pool(name))
     }
 
     fn broadcast_within_pool(&mut self, pool_name: &'static str, message: impl Message + 'static) {
@@ -62,6 +62,7 @@ impl SimulationAccess {
             self.process_on_execution,
             Destination::BroadcastWithinPool(pool_name),
             Rc::new(message),
+            cpu::elapsed(),
         ));
     }
 
@@ -70,6 +71,7 @@ impl SimulationAccess {
             self.process_on_execution,
             Destination::Broadcast,
             Rc::new(message),
+            cpu::elapsed(),
         ));
     }
 
@@ -78,6 +80,7 @@ impl SimulationAccess {
             self.process_on_execution,
             Destination::To(to),
             Rc::new(message),
+            cpu::elapsed(),
         ));
     }
 
@@ -88,11 +91,18 @@ impl SimulationAccess {
 
     fn schedule_timer_after(&mut self, after: Jiffies) -> TimerId {
         let timer_id = next_timer_id();
+        let after = skew::local_to_global(self.process_on_execution, after);
         self.scheduled_timers
-            .push((self.process_on_execution, timer_id, after));
+            .push((self.process_on_execution, timer_id, cpu::elapsed() + after));
         timer_id
     }
 
+    // Completion events of storage operations travel through the timer manager
+    fn schedule_completion(&mut self, id: TimerId, after: Jiffies) {
+        self.scheduled_timers
+            .push((self.process_on_execution, id, cpu::elapsed() + after));
+    }
+
     fn drain(&mut self) {
         drain_to(&self.network, &mut self.scheduled_messages);
         drain_to(&self.timers, &mut self.scheduled_timers);
@@ -110,7 +120,7 @@ impl SimulationAccess {
 // Any actor makes step -> Buffering outcoming events -> Drain them to all actors
 // Before any process step actor should ensure corrent ProcessId on execution via access::set_process()
 thread_local! {
-    pub(crate) static ACCESS_HANDLE: RefCell<Option<SimulationAccess>> = RefCell::new(None);
+    pub(crate) static ACCESS_HANDLE: RefCell<Option<SimulationAccess>> = const { RefCell::new(None) };
 }
 
 pub(crate) fn drop_access() {
@@ -135,6 +145,49 @@ where
     ACCESS_HANDLE.with_borrow_mut(|access| f(access.as_mut().expect("Out of simulation context")))
 }
 
+// Process-level randomness (choose_from_pool) continues from here in a fork
+pub(crate) fn save_access_random() -> Randomizer {
+    with_access(|access| access.random.clone())
+}
+
+// Number of sends buffered so far, marks the start of a handler for take_sends()
+pub(crate) fn buffered_sends() -> usize {
+    with_access(|access| access.scheduled_messages.len())
+}
+
+// Takes sends buffered since the mark back from the network, see helpers::link
+pub(crate) fn take_sends(mark: usize) -> Vec<(Destination, Rc<dyn Message>, Jiffies)> {
+    with_access(|access| {
+        access
+            .scheduled_messages
+            .split_off(mark)
+            .into_iter()
+            .map(|(_, destination, message, departure)| (destination, message, departure))
+            .collect()
+    })
+}
+
+// Sends a message as the current process, keeping the departure of a taken send
+pub(crate) fn push_send(to: ProcessId, message: Rc<dyn Message>, departure: Jiffies) {
+    with_access(|access| {
+        access.scheduled_messages.push((
+            access.process_on_execution,
+            Destination::To(to),
+            message,
+            departure,
+        ))
+    });
+}
+
+// Processes a message to the destination is delivered to
+pub(crate) fn resolve_destination(destination: &Destination) -> Vec<ProcessId> {
+    with_access(|access| match destination {
+        Destination::Broadcast => access.topology.processes().collect(),
+        Destination::BroadcastWithinPool(pool) => access.topology.list_pool(pool).to_vec(),
+        Destination::To(to) => vec![*to],
+    })
+}
+
 pub(crate) fn set_process(id: ProcessId) {
     with_access(|access| access.set_process(id));
 }
@@ -143,6 +196,10 @@ pub(crate) fn schedule() {
     with_access(|access| access.drain());
 }
 
+pub(crate) fn schedule_completion(id: TimerId, after: Jiffies) {
+    with_access(|access| access.schedule_completion(id, after));
+}
+
 pub fn schedule_timer_after(after: Jiffies) -> TimerId {
     with_access(|access| access.schedule_timer_after(after))
 }
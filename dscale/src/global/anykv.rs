@@ -7,6 +7,9 @@
 //! The storage is thread-local and persists throughout the simulation lifetime.
 //! All functions operate on a per-simulation basis and are reset when a new
 //! simulation starts.
+//!
+//! Values are not part of a [`Snapshot`](crate::Snapshot): forks of a snapshot
+//! share the store with each other and with the host.
 
 use std::any::Any;
 use std::cell::RefCell;
@@ -100,6 +103,32 @@ pub fn get<T: 'static + Clone>(key: &str) -> T {
     })
 }
 
+/// Retrieves a cloned copy of a value if the key is present.
+///
+/// Useful for optional parameters: unlike [`get`], a missing key is not an
+/// error.
+///
+/// # Examples
+///
+/// ```rust
+/// use dscale::global::anykv;
+///
+/// anykv::set("verbose", true);
+/// assert_eq!(anykv::try_get::<bool>("verbose"), Some(true));
+/// assert_eq!(anykv::try_get::<bool>("quiet"), None);
+/// ```
+///
+/// # Panics
+///
+/// This function panics if the stored value cannot be downcast to type `T`.
+pub fn try_get<T: 'static + Clone>(key: &str) -> Option<T> {
+    ANY_KV.with(|m| {
+        m.borrow()
+            .get(key)
+            .map(|value| value.downcast_ref::<T>().cloned().expect("Wrong type cast"))
+    })
+}
+
 /// Modifies a value in the global key-value store in-place.
 ///
 /// This function allows you to modify a stored value without retrieving and
@@ -2,20 +2,33 @@
 //!
 //! This module provides access to the current simulation time through a thread-local
 //! storage mechanism. The clock is managed internally by the simulation engine and
-//! provides deterministic time progression for all processes.
+//! provides deterministic time progression for all processes. It also keeps the
+//! real-time length of a jiffy declared for the simulation.
 
-use std::cell::Cell;
+use std::{cell::Cell, time::Duration};
 
 use log::debug;
 
 use crate::Jiffies;
 
 thread_local! {
-    pub(crate) static CLOCK: Cell<Jiffies> = Cell::new(Jiffies(0))
+    pub(crate) static CLOCK: Cell<Jiffies> = const { Cell::new(Jiffies(0)) };
+    // Unset while no simulation exists on the thread
+    static JIFFY: Cell<Option<Duration>> = const { Cell::new(None) };
 }
 
 pub(crate) fn drop_clock() {
     CLOCK.take();
+    JIFFY.take();
+}
+
+pub(crate) fn setup_jiffy(jiffy: Duration) {
+    JIFFY.set(Some(jiffy));
+}
+
+// Sets the clock of a forked simulation, which may be behind the current one
+pub(crate) fn restore_clock(present: Jiffies) {
+    CLOCK.set(present);
 }
 
 pub(crate) fn fast_forward_clock(future: Jiffies) {
@@ -66,3 +79,37 @@ pub(crate) fn fast_forward_clock(future: Jiffies) {
 pub fn now() -> Jiffies {
     CLOCK.get()
 }
+
+/// Returns the real-time length of one jiffy of the current simulation.
+///
+/// Declared with [`SimulationBuilder::jiffy`], one millisecond when not
+/// declared. Conversions between [`Jiffies`] and [`Duration`] and bandwidths
+/// given in bits per second use it.
+///
+/// [`SimulationBuilder::jiffy`]: crate::SimulationBuilder::jiffy
+///
+/// # Panics
+///
+/// Panics if no simulation exists on this thread: before a
+/// [`SimulationBuilder`] is built or after its simulation is dropped. To
+/// configure a builder in real time, hand it [`Duration`]s instead.
+///
+/// [`SimulationBuilder`]: crate::SimulationBuilder
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use dscale::{SimulationBuilder, global::jiffy};
+///
+/// let _simulation = SimulationBuilder::default()
+///     .jiffy(Duration::from_micros(100))
+///     .build();
+/// assert_eq!(jiffy(), Duration::from_micros(100));
+/// ```
+pub fn jiffy() -> Duration {
+    JIFFY
+        .get()
+        .expect("No simulation on this thread to take the length of a jiffy from")
+}
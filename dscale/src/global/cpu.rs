@@ -0,0 +1,141 @@
+//! Virtual CPU time of process handlers.
+//!
+//! Handlers execute instantly by default. A handler may declare that it burns
+//! simulated CPU time with [`consume_cpu`], and a message may carry a fixed
+//! receive-side cost with [`Message::cpu_cost`]. Spending CPU time has three
+//! effects on the process:
+//!
+//! - Messages sent and timers scheduled by the handler depart only once the CPU
+//!   time consumed so far has elapsed.
+//! - The handler occupies one of the process's cores until its total CPU time
+//!   has elapsed. Processes have a single core unless configured otherwise
+//!   with [`SimulationBuilder::cores`].
+//! - Messages and timers due while all cores of the process are busy wait
+//!   until one of them is free again, keeping their relative order.
+//!
+//! Handlers still execute atomically at the time they start, so the core model
+//! only affects timing: with several cores, independent handler invocations
+//! overlap in simulated time instead of queueing behind each other.
+//!
+//! [`Message::cpu_cost`]: crate::Message::cpu_cost
+//! [`SimulationBuilder::cores`]: crate::SimulationBuilder::cores
+
+use std::{cell::RefCell, collections::BTreeMap};
+
+use log::debug;
+
+use crate::{Jiffies, ProcessId, now};
+
+#[derive(Clone, Default)]
+pub(crate) struct Cpu {
+    // Consumed by the handler on execution
+    consumed: Jiffies,
+    // Time at which each core of the process becomes free
+    cores: BTreeMap<ProcessId, Vec<Jiffies>>,
+}
+
+impl Cpu {
+    fn cores(&mut self, id: ProcessId) -> &mut Vec<Jiffies> {
+        self.cores.entry(id).or_insert_with(|| vec![Jiffies(0)])
+    }
+}
+
+thread_local! {
+    static CPU: RefCell<Cpu> = RefCell::new(Cpu::default());
+}
+
+pub(crate) fn setup_cpu(cores: &BTreeMap<ProcessId, usize>) {
+    CPU.set(Cpu {
+        consumed: Jiffies(0),
+        cores: cores
+            .iter()
+            .map(|(id, count)| (*id, vec![Jiffies(0); *count]))
+            .collect(),
+    });
+}
+
+pub(crate) fn save_cpu() -> Cpu {
+    CPU.with_borrow(|cpu| cpu.clone())
+}
+
+pub(crate) fn restore_cpu(cpu: Cpu) {
+    CPU.set(cpu);
+}
+
+pub(crate) fn drop_cpu() {
+    CPU.take();
+}
+
+pub(crate) fn begin_handler() {
+    CPU.with_borrow_mut(|cpu| cpu.consumed = Jiffies(0));
+}
+
+pub(crate) fn end_handler(id: ProcessId) {
+    CPU.with_borrow_mut(|cpu| {
+        if cpu.consumed > Jiffies(0) {
+            let until = now() + cpu.consumed;
+            let core = cpu
+                .cores(id)
+                .iter_mut()
+                .min()
+                .expect("Process should have at least one core");
+            debug!("P{id} occupies a core until {until}");
+            *core = until;
+        }
+    });
+}
+
+// CPU time consumed by the current handler so far: offset of its outgoing events
+pub(crate) fn elapsed() -> Jiffies {
+    CPU.with_borrow(|cpu| cpu.consumed)
+}
+
+// Time at which the process can run its next handler, if all its cores are busy now
+pub(crate) fn busy_until(id: ProcessId) -> Option<Jiffies> {
+    CPU.with_borrow_mut(|cpu| cpu.cores(id).iter().min().copied())
+        .filter(|until| *until > now())
+}
+
+/// Consumes virtual CPU time inside the currently executing handler.
+///
+/// Calls accumulate within a handler. Everything the handler sends or
+/// schedules after the call is delayed by the total consumed so far, and the
+/// handler occupies a core of the process until all the CPU time it consumed
+/// has elapsed. Once every core is occupied, the next deliveries and timers of
+/// the process are deferred until a core becomes free.
+///
+/// Note that [`now`] keeps returning the time the handler started at.
+///
+/// # Context
+///
+/// This function must be called from within a process context (i.e., during
+/// the execution of [`ProcessHandle`] methods).
+///
+/// [`now`]: crate::now
+/// [`ProcessHandle`]: crate::ProcessHandle
+///
+/// # Examples
+///
+/// ```rust
+/// use dscale::{consume_cpu, send_to, Jiffies, Message, MessagePtr, ProcessHandle, ProcessId, TimerId};
+///
+/// struct Vote;
+/// impl Message for Vote {}
+///
+/// #[derive(Default)]
+/// struct Validator;
+///
+/// impl ProcessHandle for Validator {
+///     fn start(&mut self) {}
+///
+///     fn on_message(&mut self, from: ProcessId, _message: MessagePtr) {
+///         consume_cpu(Jiffies(2)); // Verify signature
+///         send_to(from, Vote);     // Departs 2 jiffies after delivery
+///     }
+///
+///     fn on_timer(&mut self, _id: TimerId) {}
+/// }
+/// ```
+pub fn consume_cpu(amount: Jiffies) {
+    CPU.with_borrow_mut(|cpu| cpu.consumed += amount);
+}
@@ -2,12 +2,28 @@ mod access;
 pub mod anykv;
 pub(crate) mod clock;
 pub mod configuration;
+pub(crate) mod cpu;
+pub(crate) mod order;
+pub(crate) mod skew;
 pub mod tso;
 
+use std::{cell::Cell, collections::BTreeMap, time::Duration};
+
+use crate::{Jiffies, ProcessId, storage, trace};
+
 pub use tso::global_unique_id;
 
+pub use clock::jiffy;
 pub use clock::now;
 
+pub use cpu::consume_cpu;
+
+pub use order::TieBreaking;
+
+pub use skew::ClockSkew;
+pub use skew::local_now;
+pub use skew::set_clock_skew;
+
 pub use access::broadcast;
 pub use access::broadcast_within_pool;
 pub use access::choose_from_pool;
@@ -17,15 +33,88 @@ pub use access::schedule_timer_after;
 pub use access::send_random_from_pool;
 pub use access::send_to;
 
+pub(crate) use access::buffered_sends;
+pub(crate) use access::push_send;
+pub(crate) use access::resolve_destination;
+pub(crate) use access::save_access_random;
 pub(crate) use access::schedule;
+pub(crate) use access::schedule_completion;
 pub(crate) use access::set_process;
 pub(crate) use access::setup_access;
+pub(crate) use access::take_sends;
 
 pub(crate) use clock::fast_forward_clock;
+pub(crate) use clock::setup_jiffy;
+
+pub(crate) use cpu::setup_cpu;
+
+pub(crate) use order::{TieKey, next_tie_key, setup_order};
+
+pub(crate) use skew::setup_skew;
+
+// Engine state kept in thread-locals, restored when a simulation is forked.
+// Host data in anykv is not part of it, only the configuration is set up again.
+#[derive(Clone)]
+pub(crate) struct GlobalState {
+    pub(crate) clock: Jiffies,
+    jiffy: Duration,
+    tso: usize,
+    order: order::Sequencer,
+    cpu: cpu::Cpu,
+    skew: BTreeMap<ProcessId, skew::LocalClock>,
+    storage: Option<storage::Storage>,
+    trace: Option<trace::Trace>,
+}
+
+pub(crate) fn save_all() -> GlobalState {
+    GlobalState {
+        clock: clock::now(),
+        jiffy: clock::jiffy(),
+        tso: tso::save_tso(),
+        order: order::save_order(),
+        cpu: cpu::save_cpu(),
+        skew: skew::save_skew(),
+        storage: storage::save_storage(),
+        trace: trace::save_trace(),
+    }
+}
+
+pub(crate) fn restore_all(state: &GlobalState) {
+    let state = state.clone();
+    clock::restore_clock(state.clock);
+    clock::setup_jiffy(state.jiffy);
+    tso::restore_tso(state.tso);
+    order::restore_order(state.order);
+    cpu::restore_cpu(state.cpu);
+    skew::restore_skew(state.skew);
+    storage::restore_storage(state.storage);
+    trace::restore_trace(state.trace);
+}
+
+thread_local! {
+    // Simulation owning the thread-locals: only it may run or clear them
+    static ACTIVE_SIMULATION: Cell<usize> = const { Cell::new(0) };
+    static NEXT_SIMULATION: Cell<usize> = const { Cell::new(1) };
+}
+
+pub(crate) fn activate_simulation() -> usize {
+    let id = NEXT_SIMULATION.replace(NEXT_SIMULATION.get() + 1);
+    ACTIVE_SIMULATION.set(id);
+    id
+}
+
+pub(crate) fn is_active_simulation(id: usize) -> bool {
+    ACTIVE_SIMULATION.get() == id
+}
 
 pub(crate) fn drop_all() {
     clock::drop_clock();
+    cpu::drop_cpu();
+    order::drop_order();
+    skew::drop_skew();
     tso::drop_tso();
     anykv::drop_anykv();
     access::drop_access();
+    storage::drop_storage();
+    trace::drop_trace();
 }
@@ -0,0 +1,104 @@
+//! Deterministic ordering of simultaneous events.
+//!
+//! Every event submitted to the engine (a message towards a single target or
+//! a timer) receives a [`TieKey`] when it is submitted. Events are executed in
+//! the total order `(time, tie key)`, across the network and timers alike:
+//!
+//! - With [`TieBreaking::SubmissionOrder`] (the default) events due at the same
+//!   jiffy run in the order they were submitted. Within one handler step all
+//!   outgoing messages are submitted before its timers, and broadcast targets
+//!   are submitted in ascending [`ProcessId`] order.
+//! - With [`TieBreaking::Randomized`] ties are broken by a value drawn from the
+//!   simulation seed, which shakes out order-dependent bugs while keeping runs
+//!   reproducible for a given seed.
+//!
+//! [`ProcessId`]: crate::ProcessId
+
+use std::cell::RefCell;
+
+use crate::random::{Randomizer, Seed};
+
+// Decorrelates tie ranks from the latency stream that uses the same base seed
+const TIE_SALT: Seed = 0x9E37_79B9_7F4A_7C15;
+
+/// How events scheduled for the same jiffy are ordered.
+///
+/// # Examples
+///
+/// ```rust
+/// use dscale::{SimulationBuilder, TieBreaking};
+///
+/// let builder = SimulationBuilder::default()
+///     .seed(7)
+///     .tie_breaking(TieBreaking::Randomized); // Same seed, same shuffled order
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TieBreaking {
+    /// Simultaneous events run in the order they were submitted.
+    #[default]
+    SubmissionOrder,
+    /// Simultaneous events run in a seed-derived pseudo-random order.
+    Randomized,
+}
+
+/// Secondary sort key of an event after its time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct TieKey {
+    rank: u64,
+    seq: u64,
+}
+
+#[derive(Clone)]
+pub(crate) struct Sequencer {
+    next_seq: u64,
+    randomizer: Option<Randomizer>,
+}
+
+impl Sequencer {
+    fn next(&mut self) -> TieKey {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        TieKey {
+            rank: self
+                .randomizer
+                .as_mut()
+                .map_or(seq, |randomizer| randomizer.random_u64()),
+            seq,
+        }
+    }
+}
+
+thread_local! {
+    static SEQUENCER: RefCell<Sequencer> = const {
+        RefCell::new(Sequencer {
+            next_seq: 0,
+            randomizer: None,
+        })
+    };
+}
+
+pub(crate) fn setup_order(tie_breaking: TieBreaking, seed: Seed) {
+    SEQUENCER.set(Sequencer {
+        next_seq: 0,
+        randomizer: match tie_breaking {
+            TieBreaking::SubmissionOrder => None,
+            TieBreaking::Randomized => Some(Randomizer::new(seed ^ TIE_SALT)),
+        },
+    });
+}
+
+pub(crate) fn next_tie_key() -> TieKey {
+    SEQUENCER.with_borrow_mut(|sequencer| sequencer.next())
+}
+
+pub(crate) fn save_order() -> Sequencer {
+    SEQUENCER.with_borrow(|sequencer| sequencer.clone())
+}
+
+pub(crate) fn restore_order(sequencer: Sequencer) {
+    SEQUENCER.set(sequencer);
+}
+
+pub(crate) fn drop_order() {
+    setup_order(TieBreaking::SubmissionOrder, 0);
+}
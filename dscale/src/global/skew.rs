@@ -0,0 +1,187 @@
+//! Per-process local clocks with skew and drift.
+//!
+//! [`now`] is the true global time, which no real process can observe. Each
+//! process also has a local clock, read with [`local_now`], that runs at its
+//! own rate and may be stepped forwards or backwards:
+//!
+//! ```text
+//! local(t) = local(anchor) + offset + (t - anchor) * (1 + drift)
+//! ```
+//!
+//! where `anchor` is the global time the current [`ClockSkew`] was applied at
+//! (zero for skews configured on the builder). Timers scheduled with
+//! [`schedule_timer_after`] measure their delay on the local clock of the
+//! process that scheduled them.
+//!
+//! [`now`]: crate::now
+//! [`schedule_timer_after`]: crate::schedule_timer_after
+
+use std::{cell::RefCell, collections::BTreeMap};
+
+use log::debug;
+
+use crate::{Jiffies, ProcessId, now, rank};
+
+/// Deviation of a local clock from global time.
+///
+/// # Examples
+///
+/// ```rust
+/// use dscale::ClockSkew;
+///
+/// // 50 jiffies ahead, gaining 1 jiffy every 1000
+/// let skew = ClockSkew { offset: 50, drift: 0.001 };
+///
+/// // Perfect clock
+/// assert_eq!(ClockSkew::default(), ClockSkew { offset: 0, drift: 0.0 });
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ClockSkew {
+    /// Step applied to the local clock, in jiffies. May be negative.
+    pub offset: i64,
+    /// Relative rate error: `0.01` runs 1% fast, `-0.01` runs 1% slow. Must be above `-1`.
+    pub drift: f64,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct LocalClock {
+    anchor: Jiffies,
+    local_at_anchor: f64,
+    rate: f64,
+}
+
+impl Default for LocalClock {
+    fn default() -> Self {
+        Self::at(Jiffies(0), 0.0, ClockSkew::default())
+    }
+}
+
+impl LocalClock {
+    fn at(anchor: Jiffies, local: f64, skew: ClockSkew) -> Self {
+        assert!(skew.drift > -1.0, "Clock should not run backwards");
+        Self {
+            anchor,
+            local_at_anchor: local + skew.offset as f64,
+            rate: 1.0 + skew.drift,
+        }
+    }
+
+    fn read(&self, global: Jiffies) -> f64 {
+        self.local_at_anchor + (global.0 - self.anchor.0) as f64 * self.rate
+    }
+
+    // Global delay after which this clock advances by `local`
+    fn global_delay(&self, local: Jiffies) -> Jiffies {
+        Jiffies((local.0 as f64 / self.rate).ceil() as usize)
+    }
+}
+
+thread_local! {
+    static CLOCKS: RefCell<BTreeMap<ProcessId, LocalClock>> = const { RefCell::new(BTreeMap::new()) };
+}
+
+pub(crate) fn setup_skew(skews: &BTreeMap<ProcessId, ClockSkew>) {
+    CLOCKS.set(
+        skews
+            .iter()
+            .map(|(id, skew)| (*id, LocalClock::at(Jiffies(0), 0.0, *skew)))
+            .collect(),
+    );
+}
+
+pub(crate) fn save_skew() -> BTreeMap<ProcessId, LocalClock> {
+    CLOCKS.with_borrow(|clocks| clocks.clone())
+}
+
+pub(crate) fn restore_skew(clocks: BTreeMap<ProcessId, LocalClock>) {
+    CLOCKS.set(clocks);
+}
+
+pub(crate) fn drop_skew() {
+    CLOCKS.take();
+}
+
+fn clock_of(id: ProcessId) -> LocalClock {
+    CLOCKS.with_borrow(|clocks| clocks.get(&id).copied().unwrap_or_default())
+}
+
+// Converts a delay on the local clock of the process to global time
+pub(crate) fn local_to_global(id: ProcessId, after: Jiffies) -> Jiffies {
+    clock_of(id).global_delay(after)
+}
+
+/// Returns the time shown by the local clock of the current process.
+///
+/// Without a configured [`ClockSkew`] it is equal to [`now`]. Local time never
+/// goes below zero, but it may go backwards if the clock is stepped back.
+///
+/// # Context
+///
+/// This function must be called from within a process context (i.e., during
+/// the execution of [`ProcessHandle`] methods).
+///
+/// [`now`]: crate::now
+/// [`ProcessHandle`]: crate::ProcessHandle
+///
+/// # Examples
+///
+/// ```rust
+/// use dscale::{local_now, now, Jiffies, MessagePtr, ProcessHandle, ProcessId, TimerId};
+///
+/// #[derive(Default)]
+/// struct LeaseHolder {
+///     lease_expires: Jiffies,
+/// }
+///
+/// impl ProcessHandle for LeaseHolder {
+///     fn start(&mut self) {
+///         self.lease_expires = local_now() + Jiffies(100);
+///     }
+///
+///     fn on_message(&mut self, _from: ProcessId, _message: MessagePtr) {
+///         let lease_valid = local_now() < self.lease_expires; // Not now()!
+///     }
+///
+///     fn on_timer(&mut self, _id: TimerId) {}
+/// }
+/// ```
+pub fn local_now() -> Jiffies {
+    Jiffies(clock_of(rank()).read(now()).max(0.0) as usize)
+}
+
+/// Changes the clock of a process at runtime.
+///
+/// The clock is stepped by `skew.offset` from its current reading and runs
+/// with rate `1 + skew.drift` from now on. Timers that are already scheduled
+/// keep their deadlines. Intended for nemesis processes injecting clock faults.
+///
+/// # Examples
+///
+/// ```rust
+/// use dscale::{set_clock_skew, ClockSkew, Jiffies, MessagePtr, ProcessHandle, ProcessId, TimerId};
+/// use dscale::schedule_timer_after;
+///
+/// #[derive(Default)]
+/// struct Nemesis;
+///
+/// impl ProcessHandle for Nemesis {
+///     fn start(&mut self) {
+///         schedule_timer_after(Jiffies(1000));
+///     }
+///
+///     fn on_message(&mut self, _from: ProcessId, _message: MessagePtr) {}
+///
+///     fn on_timer(&mut self, _id: TimerId) {
+///         // Step P1 back by 200 jiffies and make it run 5% slow
+///         set_clock_skew(1, ClockSkew { offset: -200, drift: -0.05 });
+///     }
+/// }
+/// ```
+pub fn set_clock_skew(process: ProcessId, skew: ClockSkew) {
+    let present = now();
+    let local = clock_of(process).read(present);
+    debug!("Skewing clock of P{process} at local time {local}: {skew:?}");
+    CLOCKS.with_borrow_mut(|clocks| {
+        clocks.insert(process, LocalClock::at(present, local, skew));
+    });
+}
@@ -11,7 +11,7 @@
 use std::cell::Cell;
 
 thread_local! {
-    pub(crate) static TSO: Cell<usize> = Cell::new(0)
+    pub(crate) static TSO: Cell<usize> = const { Cell::new(0) }
 }
 
 /// Generates a globally unique identifier within the simulation.
@@ -82,6 +82,14 @@ pub fn global_unique_id() -> usize {
     TSO.replace(TSO.get() + 1)
 }
 
+pub(crate) fn save_tso() -> usize {
+    TSO.get()
+}
+
+pub(crate) fn restore_tso(next: usize) {
+    TSO.set(next);
+}
+
 pub(crate) fn drop_tso() {
     TSO.take();
 }
@@ -5,8 +5,6 @@
 //! for implementing quorum-based algorithms, consensus protocols, and other
 //! distributed system patterns that require waiting for multiple responses.
 
-use std::usize;
-
 /// A compile-time sized collector for gathering multiple values.
 ///
 /// `Combiner` is designed for scenarios where you need to collect exactly `K`
@@ -322,6 +320,10 @@ impl<T: Sized, const K: usize> Combiner<T, K> {
     /// - Memory is pre-allocated on the stack for efficiency
     /// - The operation is O(1) with no heap allocations
     pub fn combine(&mut self, value: T) -> Option<[T; K]> {
+        if self.idx >= K {
+            return None;
+        }
+
         self.quorum[self.idx] = Some(value);
         self.idx += 1;
 
@@ -335,3 +337,9 @@ impl<T: Sized, const K: usize> Combiner<T, K> {
         }
     }
 }
+
+impl<T: Sized, const K: usize> Default for Combiner<T, K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
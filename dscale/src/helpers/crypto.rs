@@ -0,0 +1,199 @@
+//! Sizes and CPU costs of cryptographic primitives.
+//!
+//! Simulated protocols do not sign anything, yet signatures dominate the size
+//! of their messages and the CPU time of their handlers. A
+//! [`SignatureScheme`] describes a scheme once: key and signature sizes,
+//! whether and how signatures aggregate, and the time it takes to sign,
+//! verify and aggregate. Messages derive their
+//! [`virtual_size`](crate::Message::virtual_size) and
+//! [`cpu_cost`](crate::Message::cpu_cost) from it, so switching a protocol
+//! from [`ED25519`] to [`BLS12_381`] is a one-line change.
+//!
+//! Costs are given in real time and converted with the length of a jiffy
+//! declared by [`SimulationBuilder::jiffy`](crate::SimulationBuilder::jiffy),
+//! rounding to the nearest jiffy, so they are only available while a
+//! simulation exists, e.g. in handlers. Compute the cost of a batch with a single
+//! call rather than summing single costs so that sub-jiffy costs are not lost.
+//!
+//! The presets are rough single-core figures for common implementations,
+//! meant as a starting point rather than a benchmark.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use dscale::helpers::crypto::{self, HASH_SIZE, SignatureScheme};
+//! use dscale::{Jiffies, Message, SimulationBuilder};
+//!
+//! const SCHEME: SignatureScheme = crypto::BLS12_381;
+//!
+//! struct Certificate {
+//!     signers: usize,
+//!     validators: usize,
+//! }
+//!
+//! impl Message for Certificate {
+//!     fn virtual_size(&self) -> usize {
+//!         HASH_SIZE + SCHEME.certificate_size(self.signers, self.validators)
+//!     }
+//!
+//!     fn cpu_cost(&self) -> Jiffies {
+//!         SCHEME.certificate_verify_cost(self.signers)
+//!     }
+//! }
+//!
+//! let _simulation = SimulationBuilder::default().build();
+//!
+//! let certificate = Certificate { signers: 67, validators: 100 };
+//! assert_eq!(certificate.virtual_size(), 32 + 13 + 96);
+//! assert_eq!(certificate.cpu_cost(), Jiffies(1)); // 1.167 ms with 1 ms jiffies
+//! ```
+
+use std::time::Duration;
+
+use crate::time::Jiffies;
+
+/// Size of a cryptographic hash (SHA-256, BLAKE3) in bytes.
+pub const HASH_SIZE: usize = 32;
+
+/// How signatures of several signers combine into a certificate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Aggregation {
+    /// Signatures do not combine: a certificate carries every signature.
+    None,
+    /// Signatures on the same message aggregate into one signature; a
+    /// certificate carries it together with a bitmap of the signers, whose
+    /// public keys are aggregated to verify it.
+    Multi,
+    /// Signature shares of any threshold of signers combine into one
+    /// signature verifiable with a single group key; a certificate carries
+    /// only that signature.
+    Threshold,
+}
+
+/// Sizes and costs of a signature scheme.
+///
+/// # Examples
+///
+/// A scheme not covered by the presets:
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use dscale::helpers::crypto::{self, SignatureScheme};
+///
+/// const SECP256K1: SignatureScheme = SignatureScheme {
+///     name: "ECDSA secp256k1",
+///     public_key_size: 33,
+///     signature_size: 64,
+///     sign: Duration::from_micros(30),
+///     verify: Duration::from_micros(60),
+///     ..crypto::ED25519
+/// };
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SignatureScheme {
+    pub name: &'static str,
+    pub public_key_size: usize,
+    /// Size of a single signature or signature share
+    pub signature_size: usize,
+    pub aggregation: Aggregation,
+    /// Time to produce a signature or signature share
+    pub sign: Duration,
+    /// Time to verify a single signature or signature share
+    pub verify: Duration,
+    /// Time to add one signature to an aggregate, or to combine one share
+    /// into a threshold signature
+    pub aggregate: Duration,
+    /// Time per signer to aggregate public keys when verifying a
+    /// multi-signature
+    pub aggregate_key: Duration,
+}
+
+/// Ed25519 (also representative of ECDSA and Schnorr): small and fast, does
+/// not aggregate.
+pub const ED25519: SignatureScheme = SignatureScheme {
+    name: "Ed25519",
+    public_key_size: 32,
+    signature_size: 64,
+    aggregation: Aggregation::None,
+    sign: Duration::from_micros(20),
+    verify: Duration::from_micros(50),
+    aggregate: Duration::ZERO,
+    aggregate_key: Duration::ZERO,
+};
+
+/// BLS over BLS12-381 with public keys in G1 and aggregated
+/// multi-signatures: verification costs two pairings regardless of the
+/// number of signers.
+pub const BLS12_381: SignatureScheme = SignatureScheme {
+    name: "BLS12-381",
+    public_key_size: 48,
+    signature_size: 96,
+    aggregation: Aggregation::Multi,
+    sign: Duration::from_micros(400),
+    verify: Duration::from_micros(1_100),
+    aggregate: Duration::from_micros(2),
+    aggregate_key: Duration::from_micros(1),
+};
+
+/// Threshold BLS over BLS12-381: shares are combined by Lagrange
+/// interpolation into a signature of the group key.
+pub const BLS12_381_THRESHOLD: SignatureScheme = SignatureScheme {
+    name: "BLS12-381 threshold",
+    aggregation: Aggregation::Threshold,
+    aggregate: Duration::from_micros(150),
+    aggregate_key: Duration::ZERO,
+    ..BLS12_381
+};
+
+impl SignatureScheme {
+    /// Size in bytes of a certificate carrying `signers` signatures out of
+    /// `total` possible signers.
+    ///
+    /// A bitmap of the signers is included unless the scheme is
+    /// [`Aggregation::Threshold`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use dscale::helpers::crypto::{BLS12_381, BLS12_381_THRESHOLD, ED25519};
+    ///
+    /// assert_eq!(ED25519.certificate_size(3, 4), 1 + 3 * 64);
+    /// assert_eq!(BLS12_381.certificate_size(3, 4), 1 + 96);
+    /// assert_eq!(BLS12_381_THRESHOLD.certificate_size(3, 4), 96);
+    /// ```
+    pub fn certificate_size(&self, signers: usize, total: usize) -> usize {
+        let bitmap = total.div_ceil(8);
+        match self.aggregation {
+            Aggregation::None => bitmap + signers * self.signature_size,
+            Aggregation::Multi => bitmap + self.signature_size,
+            Aggregation::Threshold => self.signature_size,
+        }
+    }
+
+    /// CPU time to produce a signature.
+    pub fn sign_cost(&self) -> Jiffies {
+        Jiffies::from_duration(self.sign)
+    }
+
+    /// CPU time to verify `signatures` separate signatures or shares.
+    pub fn verify_cost(&self, signatures: usize) -> Jiffies {
+        Jiffies::from_duration(self.verify * signatures as u32)
+    }
+
+    /// CPU time to combine `signatures` signatures or shares into a
+    /// certificate. Zero for schemes that do not aggregate.
+    pub fn aggregate_cost(&self, signatures: usize) -> Jiffies {
+        Jiffies::from_duration(self.aggregate * signatures as u32)
+    }
+
+    /// CPU time to verify a certificate of `signers` signers.
+    pub fn certificate_verify_cost(&self, signers: usize) -> Jiffies {
+        let cost = match self.aggregation {
+            Aggregation::None => self.verify * signers as u32,
+            Aggregation::Multi => self.verify + self.aggregate_key * signers as u32,
+            Aggregation::Threshold => self.verify,
+        };
+        Jiffies::from_duration(cost)
+    }
+}
@@ -2,6 +2,6 @@
 #[macro_export]
 macro_rules! debug_process {
     ($($arg:tt)+) => {
-        log::debug!("[Now: {} | P{}] {}", now(), rank(), format_args!($($arg)+));
+        log::debug!("[Now: {} | P{}] {}", $crate::now(), $crate::rank(), format_args!($($arg)+));
     }
 }
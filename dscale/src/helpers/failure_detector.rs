@@ -0,0 +1,348 @@
+//! Heartbeat-based failure detection.
+//!
+//! A [`FailureDetector`] lives in the monitoring process, sends a
+//! [`Heartbeat`] to each of its peers every `interval` and watches the
+//! heartbeats coming back. It reports a [`FailureEvent::Suspected`] when a
+//! peer goes quiet and a [`FailureEvent::Restored`] when a suspected peer
+//! turns out to be alive. How a peer becomes suspected depends on the
+//! [`Mode`]: a fixed timeout, a timeout that grows with every mistake, or the
+//! phi accrual suspicion level computed from the observed heartbeat arrivals.
+//!
+//! Heartbeats are ordinary messages, so they are delayed by the simulated
+//! latency, bandwidth and CPU time, and timeouts are measured on the local
+//! clock of the process (see [`local_now`](crate::local_now)).
+//!
+//! # Examples
+//!
+//! Process 3 stops working at time 500 and the others notice:
+//!
+//! ```rust
+//! use dscale::helpers::failure_detector::{FailureDetector, FailureEvent, Mode};
+//! use dscale::*;
+//!
+//! #[derive(Default)]
+//! struct Node {
+//!     detector: Option<FailureDetector>,
+//! }
+//!
+//! impl Node {
+//!     fn crashed(&self) -> bool {
+//!         rank() == 3 && now() >= Jiffies(500)
+//!     }
+//! }
+//!
+//! impl ProcessHandle for Node {
+//!     fn start(&mut self) {
+//!         let mode = Mode::EventuallyPerfect {
+//!             timeout: Jiffies(100),
+//!             increment: Jiffies(50),
+//!         };
+//!         let mut detector = FailureDetector::new(list_pool("nodes"), Jiffies(20), mode);
+//!         detector.start();
+//!         self.detector = Some(detector);
+//!     }
+//!
+//!     fn on_message(&mut self, from: ProcessId, message: MessagePtr) {
+//!         if !self.crashed() {
+//!             self.detector.as_mut().unwrap().on_message(from, &message);
+//!         }
+//!     }
+//!
+//!     fn on_timer(&mut self, id: TimerId) {
+//!         if self.crashed() {
+//!             return;
+//!         }
+//!         for event in self.detector.as_mut().unwrap().on_timer(id) {
+//!             if let FailureEvent::Suspected(peer) = event {
+//!                 global::anykv::modify::<Vec<(ProcessId, ProcessId)>>("suspicions", |s| {
+//!                     s.push((rank(), peer))
+//!                 });
+//!             }
+//!         }
+//!     }
+//! }
+//!
+//! let mut simulation = SimulationBuilder::default()
+//!     .add_pool::<Node>("nodes", 3)
+//!     .latency_topology(&[LatencyDescription::WithinPool(
+//!         "nodes",
+//!         Distributions::Uniform(Jiffies(5), Jiffies(15)),
+//!     )])
+//!     .time_budget(Jiffies(1_000))
+//!     .build();
+//! global::anykv::set::<Vec<(ProcessId, ProcessId)>>("suspicions", Vec::new());
+//! simulation.run_until(Jiffies(1_000));
+//!
+//! let mut suspicions = global::anykv::get::<Vec<(ProcessId, ProcessId)>>("suspicions");
+//! suspicions.sort();
+//! assert_eq!(suspicions, vec![(1, 3), (2, 3)]);
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use crate::{
+    Message, MessagePtr, ProcessId, TimerId, local_now, rank, schedule_timer_after, send_to,
+    time::Jiffies,
+};
+
+/// Heartbeat exchanged by [`FailureDetector`]s.
+pub struct Heartbeat;
+
+impl Message for Heartbeat {}
+
+/// How a [`FailureDetector`] decides that a peer is suspected.
+///
+/// Peers are checked every heartbeat interval, so a peer is suspected up to
+/// one interval after its condition holds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    /// Suspects a peer after `timeout` without heartbeats, for good. Never
+    /// wrong only if `timeout` bounds the heartbeat interval plus the message
+    /// delay, i.e. in a synchronous system.
+    Perfect { timeout: Jiffies },
+    /// Suspects a peer after `timeout` without heartbeats. A heartbeat from a
+    /// suspected peer restores it and increases its timeout by `increment`,
+    /// so after the network stabilizes the detector stops making mistakes.
+    EventuallyPerfect {
+        timeout: Jiffies,
+        increment: Jiffies,
+    },
+    /// Suspects a peer when its phi, the suspicion level derived from the
+    /// mean and standard deviation of the last `window` heartbeat
+    /// inter-arrival times, exceeds `threshold`. A phi of 1 means a 10%
+    /// chance of the suspicion being wrong, 2 means 1%, 3 means 0.1% and so
+    /// on. `min_std_deviation` keeps phi from exploding on a network without
+    /// jitter. A heartbeat from a suspected peer restores it.
+    PhiAccrual {
+        threshold: f64,
+        window: usize,
+        min_std_deviation: Jiffies,
+    },
+}
+
+/// Change of the suspicion of a peer, reported by a [`FailureDetector`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FailureEvent {
+    Suspected(ProcessId),
+    Restored(ProcessId),
+}
+
+// What a detector knows about a single peer
+#[derive(Clone)]
+struct Monitor {
+    last_heartbeat: Jiffies,
+    timeout: Jiffies,
+    // Recent inter-arrival times, only updated in phi accrual mode
+    arrivals: VecDeque<usize>,
+}
+
+/// Heartbeat exchange and suspicions of a single process.
+///
+/// Embedded in the monitoring process, see
+/// [embedding a component](crate::helpers#embedding-a-component). Every peer
+/// is assumed alive on start.
+///
+/// See the [module documentation](self) for an example.
+#[derive(Clone)]
+pub struct FailureDetector {
+    mode: Mode,
+    interval: Jiffies,
+    monitors: BTreeMap<ProcessId, Monitor>,
+    suspected: BTreeSet<ProcessId>,
+    // Heartbeat timer, None before start
+    timer: Option<TimerId>,
+}
+
+impl FailureDetector {
+    /// Creates a detector monitoring `peers`.
+    ///
+    /// # Arguments
+    ///
+    /// * `peers` - Processes to exchange heartbeats with, the process itself
+    ///   is skipped if listed
+    /// * `interval` - Time between two heartbeats sent to every peer
+    /// * `mode` - When a peer becomes suspected
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero or if the phi accrual `window` is zero.
+    pub fn new(peers: impl IntoIterator<Item = ProcessId>, interval: Jiffies, mode: Mode) -> Self {
+        assert!(
+            interval > Jiffies(0),
+            "Heartbeat interval should be positive"
+        );
+        let timeout = match mode {
+            Mode::Perfect { timeout } | Mode::EventuallyPerfect { timeout, .. } => timeout,
+            Mode::PhiAccrual { window, .. } => {
+                assert!(window > 0, "Phi accrual window should not be empty");
+                Jiffies(0)
+            }
+        };
+
+        Self {
+            mode,
+            interval,
+            monitors: peers
+                .into_iter()
+                .map(|peer| {
+                    let monitor = Monitor {
+                        last_heartbeat: Jiffies(0),
+                        timeout,
+                        // Until heartbeats arrive, they are expected every interval
+                        arrivals: VecDeque::from([interval.0]),
+                    };
+                    (peer, monitor)
+                })
+                .collect(),
+            suspected: BTreeSet::new(),
+            timer: None,
+        }
+    }
+
+    /// Sends the first heartbeats and starts watching the peers.
+    ///
+    /// Must be called from within a process context.
+    pub fn start(&mut self) {
+        self.monitors.remove(&rank());
+        let now = local_now();
+        self.monitors
+            .values_mut()
+            .for_each(|monitor| monitor.last_heartbeat = now);
+        self.beat();
+    }
+
+    /// Handles a message, returning [`FailureEvent::Restored`] when it is a
+    /// heartbeat of a suspected peer that the mode allows to restore.
+    pub fn on_message(&mut self, from: ProcessId, message: &MessagePtr) -> Option<FailureEvent> {
+        if !message.is::<Heartbeat>() {
+            return None;
+        }
+        let monitor = self.monitors.get_mut(&from)?;
+
+        let now = local_now();
+        if let Mode::PhiAccrual { window, .. } = self.mode {
+            if monitor.arrivals.len() == window {
+                monitor.arrivals.pop_front();
+            }
+            monitor
+                .arrivals
+                .push_back(now.saturating_sub(monitor.last_heartbeat).0);
+        }
+        monitor.last_heartbeat = now;
+
+        match self.mode {
+            Mode::Perfect { .. } => None,
+            Mode::EventuallyPerfect { increment, .. } => self.suspected.remove(&from).then(|| {
+                monitor.timeout += increment;
+                FailureEvent::Restored(from)
+            }),
+            Mode::PhiAccrual { .. } => self
+                .suspected
+                .remove(&from)
+                .then_some(FailureEvent::Restored(from)),
+        }
+    }
+
+    /// Handles a timer: sends the next heartbeats and returns the peers that
+    /// became suspected, as [`FailureEvent::Suspected`] in ascending id order.
+    ///
+    /// Returns nothing for timers that do not belong to the detector.
+    pub fn on_timer(&mut self, id: TimerId) -> Vec<FailureEvent> {
+        if self.timer != Some(id) {
+            return Vec::new();
+        }
+        self.beat();
+
+        let now = local_now();
+        let suspects: Vec<ProcessId> = self
+            .monitors
+            .iter()
+            .filter(|(peer, _)| !self.suspected.contains(peer))
+            .filter(|(_, monitor)| self.is_late(monitor, now))
+            .map(|(peer, _)| *peer)
+            .collect();
+
+        self.suspected.extend(suspects.iter().copied());
+        suspects.into_iter().map(FailureEvent::Suspected).collect()
+    }
+
+    /// Whether the peer is currently suspected.
+    pub fn is_suspected(&self, peer: ProcessId) -> bool {
+        self.suspected.contains(&peer)
+    }
+
+    /// Currently suspected peers in ascending id order.
+    pub fn suspected(&self) -> impl Iterator<Item = ProcessId> + '_ {
+        self.suspected.iter().copied()
+    }
+
+    /// Peers that are not suspected in ascending id order.
+    ///
+    /// The first of them is a common choice of a leader.
+    pub fn alive(&self) -> impl Iterator<Item = ProcessId> + '_ {
+        self.monitors
+            .keys()
+            .copied()
+            .filter(|peer| !self.suspected.contains(peer))
+    }
+
+    /// Suspicion level of a peer, as defined for [`Mode::PhiAccrual`].
+    ///
+    /// Available in every mode, computed from the heartbeat interval until
+    /// inter-arrival times are collected. Must be called from within a
+    /// process context.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the process is not a monitored peer.
+    pub fn phi(&self, peer: ProcessId) -> f64 {
+        let monitor = self.monitors.get(&peer).expect("Peer should be monitored");
+        self.phi_of(monitor, local_now())
+    }
+
+    fn beat(&mut self) {
+        self.monitors
+            .keys()
+            .for_each(|peer| send_to(*peer, Heartbeat));
+        self.timer = Some(schedule_timer_after(self.interval));
+    }
+
+    fn is_late(&self, monitor: &Monitor, now: Jiffies) -> bool {
+        match self.mode {
+            Mode::Perfect { .. } | Mode::EventuallyPerfect { .. } => {
+                now.saturating_sub(monitor.last_heartbeat) > monitor.timeout
+            }
+            Mode::PhiAccrual { threshold, .. } => self.phi_of(monitor, now) > threshold,
+        }
+    }
+
+    fn phi_of(&self, monitor: &Monitor, now: Jiffies) -> f64 {
+        let min_std_deviation = match self.mode {
+            Mode::PhiAccrual {
+                min_std_deviation, ..
+            } => min_std_deviation.0 as f64,
+            _ => self.interval.0 as f64 / 4.0,
+        };
+
+        let samples = monitor.arrivals.len() as f64;
+        let mean = monitor.arrivals.iter().sum::<usize>() as f64 / samples;
+        let variance = monitor
+            .arrivals
+            .iter()
+            .map(|arrival| (*arrival as f64 - mean).powi(2))
+            .sum::<f64>()
+            / samples;
+        let std_deviation = variance.sqrt().max(min_std_deviation).max(f64::EPSILON);
+
+        // Logistic approximation of the normal CDF, as in Hayashibara et al.
+        // and its Cassandra and Akka implementations
+        let elapsed = now.saturating_sub(monitor.last_heartbeat).0 as f64;
+        let y = (elapsed - mean) / std_deviation;
+        let e = (-y * (1.5976 + 0.070566 * y * y)).exp();
+        if elapsed > mean {
+            -(e / (1.0 + e)).log10()
+        } else {
+            -(1.0 - 1.0 / (1.0 + e)).log10()
+        }
+    }
+}
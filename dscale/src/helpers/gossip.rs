@@ -0,0 +1,433 @@
+//! Gossip (epidemic) dissemination.
+//!
+//! A [`Gossip`] component lives in every process of a pool and spreads
+//! rumors: values that should eventually reach every member of the pool.
+//! Every `interval` a process contacts `fanout` peers chosen with
+//! [`choose_from_pool`](crate::choose_from_pool). What it exchanges with them
+//! depends on the [`Mode`]:
+//!
+//! - [`Mode::Push`]: sends the rumors it learned recently (infection-style
+//!   rumor mongering, a rumor is pushed for `push_rounds` rounds).
+//! - [`Mode::Pull`]: sends a digest of the rumors it knows, the peer answers
+//!   with the rumors missing from it (anti-entropy).
+//! - [`Mode::PushPull`] does both.
+//!
+//! Every rumor is delivered to a process once, duplicates are suppressed and
+//! counted in [`GossipStats`]. Gossip messages go through
+//! [`send_to`](crate::send_to), so they share the latency and bandwidth model
+//! with [`broadcast`](crate::broadcast) and can be compared to it directly.
+//!
+//! # Examples
+//!
+//! A rumor started by process 1 reaches a pool of 50:
+//!
+//! ```rust
+//! use dscale::helpers::gossip::{Coverage, Gossip, Mode};
+//! use dscale::*;
+//!
+//! struct Block(usize);
+//! impl Message for Block {
+//!     fn virtual_size(&self) -> usize {
+//!         1_000
+//!     }
+//! }
+//!
+//! struct Node {
+//!     gossip: Gossip<Block>,
+//! }
+//!
+//! impl Default for Node {
+//!     fn default() -> Self {
+//!         Self {
+//!             gossip: Gossip::new("nodes", Mode::PushPull, Jiffies(10)).fanout(3),
+//!         }
+//!     }
+//! }
+//!
+//! impl ProcessHandle for Node {
+//!     fn start(&mut self) {
+//!         self.gossip.start();
+//!         if rank() == 1 {
+//!             let rumor = self.gossip.spread(Block(42));
+//!             global::anykv::set("rumor", rumor);
+//!         }
+//!     }
+//!
+//!     fn on_message(&mut self, from: ProcessId, message: MessagePtr) {
+//!         for (_, block) in self.gossip.on_message(from, &message) {
+//!             assert_eq!(block.0, 42);
+//!         }
+//!     }
+//!
+//!     fn on_timer(&mut self, id: TimerId) {
+//!         self.gossip.on_timer(id);
+//!     }
+//! }
+//!
+//! let mut simulation = SimulationBuilder::default()
+//!     .add_pool::<Node>("nodes", 50)
+//!     .latency_topology(&[LatencyDescription::WithinPool(
+//!         "nodes",
+//!         Distributions::Uniform(Jiffies(1), Jiffies(5)),
+//!     )])
+//!     .time_budget(Jiffies(1_000))
+//!     .build();
+//! simulation.run_until(Jiffies(1_000));
+//!
+//! let rumor = global::anykv::get("rumor");
+//! let mut coverage = Coverage::default();
+//! for id in 1..=50 {
+//!     simulation.with_process(id, |node: &mut Node| coverage.add(&node.gossip, rumor));
+//! }
+//! assert!(coverage.is_full());
+//! assert!(coverage.rounds(Jiffies(10)) < 20);
+//! ```
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    rc::Rc,
+};
+
+use crate::{
+    Message, MessagePtr, ProcessId, TimerId, choose_from_pool, global_unique_id, now, rank,
+    schedule_timer_after, send_to, time::Jiffies,
+};
+
+// Rumor id and origin time carried with every rumor
+const RUMOR_HEADER_SIZE: usize = 16;
+// Size of a rumor id in a digest
+const DIGEST_ENTRY_SIZE: usize = 8;
+
+/// Id of a rumor, unique within a simulation.
+pub type RumorId = usize;
+
+/// What gossiping processes exchange every round.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Mode {
+    Push,
+    Pull,
+    PushPull,
+}
+
+/// Message exchanged by [`Gossip`] components.
+pub struct GossipMessage<T>(Payload<T>);
+
+enum Payload<T> {
+    // Id, origin time and value
+    Rumors(Vec<(RumorId, Jiffies, Rc<T>)>),
+    Digest(BTreeSet<RumorId>),
+}
+
+impl<T: Message> Message for GossipMessage<T> {
+    fn virtual_size(&self) -> usize {
+        match &self.0 {
+            Payload::Rumors(rumors) => rumors
+                .iter()
+                .map(|(_, _, value)| RUMOR_HEADER_SIZE + value.virtual_size())
+                .sum(),
+            Payload::Digest(ids) => DIGEST_ENTRY_SIZE * ids.len(),
+        }
+    }
+}
+
+/// Traffic counters of a single [`Gossip`] component.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GossipStats {
+    /// Gossip messages sent, rumors and digests
+    pub sent_messages: usize,
+    pub sent_bytes: usize,
+    /// Rumors received for the first time
+    pub delivered: usize,
+    /// Rumors received again, suppressed
+    pub duplicates: usize,
+    /// Bytes of suppressed rumors, the price of gossip over a broadcast
+    pub redundant_bytes: usize,
+}
+
+struct Rumor<T> {
+    value: Rc<T>,
+    origin: Jiffies,
+    received: Jiffies,
+    // Rounds left to push the rumor
+    hot: usize,
+}
+
+// Derived Clone would require T: Clone
+impl<T> Clone for Rumor<T> {
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value.clone(),
+            origin: self.origin,
+            received: self.received,
+            hot: self.hot,
+        }
+    }
+}
+
+/// Gossip state of a single process.
+///
+/// Embedded in every process of the pool, see
+/// [embedding a component](crate::helpers#embedding-a-component).
+///
+/// See the [module documentation](self) for an example.
+pub struct Gossip<T> {
+    pool: &'static str,
+    mode: Mode,
+    interval: Jiffies,
+    fanout: usize,
+    push_rounds: usize,
+    rumors: BTreeMap<RumorId, Rumor<T>>,
+    stats: GossipStats,
+    timer: Option<TimerId>,
+}
+
+impl<T> Clone for Gossip<T> {
+    fn clone(&self) -> Self {
+        Self {
+            pool: self.pool,
+            mode: self.mode,
+            interval: self.interval,
+            fanout: self.fanout,
+            push_rounds: self.push_rounds,
+            rumors: self.rumors.clone(),
+            stats: self.stats,
+            timer: self.timer,
+        }
+    }
+}
+
+impl<T: Message> Gossip<T> {
+    /// Creates a component gossiping within a pool, with fanout 3 and rumors
+    /// pushed for 5 rounds.
+    ///
+    /// # Arguments
+    ///
+    /// * `pool` - Pool the peers are chosen from, the process itself is never
+    ///   chosen
+    /// * `mode` - What is exchanged every round
+    /// * `interval` - Time between two rounds
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    pub fn new(pool: &'static str, mode: Mode, interval: Jiffies) -> Self {
+        assert!(interval > Jiffies(0), "Gossip interval should be positive");
+        Self {
+            pool,
+            mode,
+            interval,
+            fanout: 3,
+            push_rounds: 5,
+            rumors: BTreeMap::new(),
+            stats: GossipStats::default(),
+            timer: None,
+        }
+    }
+
+    /// Sets the number of peers contacted every round.
+    pub fn fanout(mut self, fanout: usize) -> Self {
+        self.fanout = fanout;
+        self
+    }
+
+    /// Sets the number of rounds a newly learned rumor is pushed for.
+    ///
+    /// Only used by [`Mode::Push`] and [`Mode::PushPull`].
+    pub fn push_rounds(mut self, rounds: usize) -> Self {
+        self.push_rounds = rounds;
+        self
+    }
+
+    /// Starts the gossip rounds.
+    ///
+    /// Must be called from within a process context.
+    pub fn start(&mut self) {
+        self.timer = Some(schedule_timer_after(self.interval));
+    }
+
+    /// Starts a rumor. It is sent in the next round.
+    ///
+    /// Must be called from within a process context.
+    ///
+    /// # Returns
+    ///
+    /// The id the rumor is known by in every process.
+    pub fn spread(&mut self, value: T) -> RumorId {
+        let id = global_unique_id();
+        let now = now();
+        self.learn(id, now, Rc::new(value), now);
+        id
+    }
+
+    /// Handles a message, returning the rumors it delivered for the first
+    /// time. Pull requests of peers are answered here.
+    pub fn on_message(&mut self, from: ProcessId, message: &MessagePtr) -> Vec<(RumorId, Rc<T>)> {
+        let Some(message) = message.try_as::<GossipMessage<T>>() else {
+            return Vec::new();
+        };
+        match &message.0 {
+            Payload::Rumors(rumors) => {
+                let now = now();
+                rumors
+                    .iter()
+                    .filter_map(|(id, origin, value)| {
+                        if self.rumors.contains_key(id) {
+                            self.stats.duplicates += 1;
+                            self.stats.redundant_bytes += RUMOR_HEADER_SIZE + value.virtual_size();
+                            return None;
+                        }
+                        self.learn(*id, *origin, value.clone(), now);
+                        self.stats.delivered += 1;
+                        Some((*id, value.clone()))
+                    })
+                    .collect()
+            }
+            Payload::Digest(known) => {
+                let missing: Vec<_> = self
+                    .rumors
+                    .iter()
+                    .filter(|(id, _)| !known.contains(id))
+                    .map(|(id, rumor)| (*id, rumor.origin, rumor.value.clone()))
+                    .collect();
+                if !missing.is_empty() {
+                    self.send(from, Payload::Rumors(missing));
+                }
+                Vec::new()
+            }
+        }
+    }
+
+    /// Handles a timer, running a gossip round if it belongs to the component.
+    pub fn on_timer(&mut self, id: TimerId) {
+        if self.timer != Some(id) {
+            return;
+        }
+        self.timer = Some(schedule_timer_after(self.interval));
+
+        let peers = self.choose_peers();
+        if matches!(self.mode, Mode::Push | Mode::PushPull) {
+            let hot: Vec<_> = self
+                .rumors
+                .iter_mut()
+                .filter(|(_, rumor)| rumor.hot > 0)
+                .map(|(id, rumor)| {
+                    rumor.hot -= 1;
+                    (*id, rumor.origin, rumor.value.clone())
+                })
+                .collect();
+            if !hot.is_empty() {
+                peers
+                    .iter()
+                    .for_each(|peer| self.send(*peer, Payload::Rumors(hot.clone())));
+            }
+        }
+        if matches!(self.mode, Mode::Pull | Mode::PushPull) {
+            let known: BTreeSet<_> = self.rumors.keys().copied().collect();
+            peers
+                .iter()
+                .for_each(|peer| self.send(*peer, Payload::Digest(known.clone())));
+        }
+    }
+
+    /// Whether the process knows the rumor.
+    pub fn knows(&self, rumor: RumorId) -> bool {
+        self.rumors.contains_key(&rumor)
+    }
+
+    /// Value of a known rumor.
+    pub fn get(&self, rumor: RumorId) -> Option<Rc<T>> {
+        self.rumors.get(&rumor).map(|rumor| rumor.value.clone())
+    }
+
+    /// Simulation time the process learned the rumor at, `None` if it does
+    /// not know it.
+    pub fn received_at(&self, rumor: RumorId) -> Option<Jiffies> {
+        self.rumors.get(&rumor).map(|rumor| rumor.received)
+    }
+
+    pub fn stats(&self) -> GossipStats {
+        self.stats
+    }
+
+    fn learn(&mut self, id: RumorId, origin: Jiffies, value: Rc<T>, now: Jiffies) {
+        self.rumors.insert(
+            id,
+            Rumor {
+                value,
+                origin,
+                received: now,
+                hot: self.push_rounds,
+            },
+        );
+    }
+
+    // Up to fanout distinct peers, fewer if the pool is too small
+    fn choose_peers(&self) -> Vec<ProcessId> {
+        let me = rank();
+        let mut peers = Vec::with_capacity(self.fanout);
+        for _ in 0..self.fanout * 4 {
+            if peers.len() == self.fanout {
+                break;
+            }
+            let peer = choose_from_pool(self.pool);
+            if peer != me && !peers.contains(&peer) {
+                peers.push(peer);
+            }
+        }
+        peers
+    }
+
+    fn send(&mut self, to: ProcessId, payload: Payload<T>) {
+        let message = GossipMessage(payload);
+        self.stats.sent_messages += 1;
+        self.stats.sent_bytes += message.virtual_size();
+        send_to(to, message);
+    }
+}
+
+/// Spread of a single rumor over a set of processes.
+///
+/// Collect it after a run by adding the [`Gossip`] component of every
+/// process, e.g. through [`Simulation::with_process`](crate::Simulation::with_process).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Coverage {
+    /// Processes that know the rumor
+    pub reached: usize,
+    /// Processes that do not know the rumor
+    pub missed: usize,
+    /// Time the rumor was started at
+    pub origin: Option<Jiffies>,
+    /// Time the last of the reached processes learned the rumor at
+    pub last_receipt: Jiffies,
+}
+
+impl Coverage {
+    /// Adds the state of a single process.
+    pub fn add<T>(&mut self, gossip: &Gossip<T>, rumor: RumorId) {
+        match gossip.rumors.get(&rumor) {
+            None => self.missed += 1,
+            Some(rumor) => {
+                self.reached += 1;
+                self.origin = Some(rumor.origin);
+                self.last_receipt = self.last_receipt.max(rumor.received);
+            }
+        }
+    }
+
+    /// Whether every added process knows the rumor.
+    pub fn is_full(&self) -> bool {
+        self.reached > 0 && self.missed == 0
+    }
+
+    /// Gossip rounds of the given interval it took to reach the last of the
+    /// reached processes, rounded up.
+    pub fn rounds(&self, interval: Jiffies) -> usize {
+        let Some(origin) = self.origin else {
+            return 0;
+        };
+        self.last_receipt
+            .saturating_sub(origin)
+            .0
+            .div_ceil(interval.0)
+    }
+}
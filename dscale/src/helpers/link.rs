@@ -0,0 +1,302 @@
+//! Reliable point-to-point links.
+//!
+//! [`Reliable`] wraps a process and turns its outgoing messages into perfect
+//! links: every message gets a per-link sequence number and is retransmitted
+//! until the receiver acknowledges it, and the receiver delivers each message
+//! to the wrapped process exactly once, optionally in FIFO order per link.
+//!
+//! The layer is transparent: the wrapped process keeps calling
+//! [`send_to`](crate::send_to), [`broadcast`](crate::broadcast) and friends
+//! and receives the original messages in
+//! [`on_message`](crate::ProcessHandle::on_message), so a protocol opts in by
+//! changing only the type of its pool:
+//!
+//! ```rust,no_run
+//! use dscale::SimulationBuilder;
+//! use dscale::helpers::link::Reliable;
+//! # use dscale::{MessagePtr, ProcessHandle, ProcessId, TimerId};
+//! # #[derive(Default)]
+//! # struct Replica;
+//! # impl ProcessHandle for Replica {
+//! #     fn start(&mut self) {}
+//! #     fn on_message(&mut self, _from: ProcessId, _message: MessagePtr) {}
+//! #     fn on_timer(&mut self, _id: TimerId) {}
+//! # }
+//!
+//! let simulation = SimulationBuilder::default()
+//!     .add_pool::<Reliable<Replica>>("replicas", 5) // Was add_pool::<Replica>
+//!     .build();
+//! ```
+//!
+//! Broadcasts are sent as separate messages to every target. Processes that
+//! exchange messages with a wrapped process must be wrapped as well: a plain
+//! process cannot read the link envelopes. Messages that are not envelopes
+//! are passed to the wrapped process unchanged.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    rc::Rc,
+};
+
+use crate::{
+    Message, MessagePtr, ProcessHandle, ProcessId, TimerId, global, schedule_timer_after, send_to,
+    storage::Completion, time::Jiffies,
+};
+
+// Sequence number carried by every envelope
+const HEADER_SIZE: usize = 8;
+
+type Sequence = u64;
+
+/// Envelope of the link layer, handled by [`Reliable`].
+pub enum LinkMessage {
+    Data(Sequence, Rc<dyn Message>),
+    Ack(Sequence),
+}
+
+impl Message for LinkMessage {
+    fn virtual_size(&self) -> usize {
+        match self {
+            LinkMessage::Data(_, payload) => HEADER_SIZE + payload.virtual_size(),
+            LinkMessage::Ack(_) => HEADER_SIZE,
+        }
+    }
+
+    fn cpu_cost(&self) -> Jiffies {
+        match self {
+            LinkMessage::Data(_, payload) => payload.cpu_cost(),
+            LinkMessage::Ack(_) => Jiffies(0),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        // Data is accounted as its payload in traces and statistics
+        match self {
+            LinkMessage::Data(_, payload) => payload.name(),
+            LinkMessage::Ack(_) => std::any::type_name::<Self>(),
+        }
+    }
+}
+
+// Receiving side of a link
+#[derive(Clone, Default)]
+struct Inbound {
+    // Every sequence number below is delivered
+    next: Sequence,
+    // Received above `next`: held back payloads (FIFO) or delivered marks
+    ahead: BTreeMap<Sequence, Option<Rc<dyn Message>>>,
+}
+
+/// Process wrapper providing perfect links to the wrapped process.
+///
+/// # Type Parameters
+///
+/// * `P` - The wrapped process
+/// * `FIFO` - Deliver messages of every link in the order they were sent
+/// * `RETRANSMIT_AFTER` - Jiffies to wait for an acknowledgement before
+///   sending a message again; it is sent again until acknowledged
+///
+/// # Examples
+///
+/// A receiver behind a link that loses every other message still gets every
+/// message exactly once and in order:
+///
+/// ```rust
+/// use dscale::helpers::link::Reliable;
+/// use dscale::*;
+///
+/// struct Number(usize);
+/// impl Message for Number {}
+///
+/// #[derive(Default)]
+/// struct Node {
+///     received: Vec<usize>,
+/// }
+///
+/// impl ProcessHandle for Node {
+///     fn start(&mut self) {
+///         if rank() == 1 {
+///             (0..10).for_each(|n| send_to(2, Number(n)));
+///         }
+///     }
+///     fn on_message(&mut self, _from: ProcessId, message: MessagePtr) {
+///         self.received.push(message.as_type::<Number>().0);
+///     }
+///     fn on_timer(&mut self, _id: TimerId) {}
+/// }
+///
+/// // Drops every other link message before it reaches the link layer
+/// #[derive(Default)]
+/// struct Lossy {
+///     link: Reliable<Node, true>,
+///     incoming: usize,
+/// }
+///
+/// impl ProcessHandle for Lossy {
+///     fn start(&mut self) {
+///         self.link.start();
+///     }
+///     fn on_message(&mut self, from: ProcessId, message: MessagePtr) {
+///         self.incoming += 1;
+///         if self.incoming % 2 == 0 {
+///             self.link.on_message(from, message);
+///         }
+///     }
+///     fn on_timer(&mut self, id: TimerId) {
+///         self.link.on_timer(id);
+///     }
+/// }
+///
+/// let mut simulation = SimulationBuilder::default()
+///     .add_pool::<Lossy>("nodes", 2)
+///     .latency_topology(&[LatencyDescription::WithinPool(
+///         "nodes",
+///         Distributions::Uniform(Jiffies(1), Jiffies(20)),
+///     )])
+///     .time_budget(Jiffies(10_000))
+///     .build();
+/// simulation.run_until(Jiffies(10_000));
+///
+/// let received = simulation.with_process(2, |lossy: &mut Lossy| lossy.link.inner().received.clone());
+/// assert_eq!(received, (0..10).collect::<Vec<_>>());
+/// ```
+#[derive(Clone)]
+pub struct Reliable<P, const FIFO: bool = false, const RETRANSMIT_AFTER: usize = 100> {
+    inner: P,
+    next_sequence: HashMap<ProcessId, Sequence>,
+    // Sent but not yet acknowledged, by destination and sequence number
+    unacked: BTreeMap<(ProcessId, Sequence), Rc<dyn Message>>,
+    retransmissions: HashMap<TimerId, (ProcessId, Sequence)>,
+    inbound: HashMap<ProcessId, Inbound>,
+}
+
+impl<P: Default, const FIFO: bool, const RETRANSMIT_AFTER: usize> Default
+    for Reliable<P, FIFO, RETRANSMIT_AFTER>
+{
+    fn default() -> Self {
+        Self {
+            inner: P::default(),
+            next_sequence: HashMap::new(),
+            unacked: BTreeMap::new(),
+            retransmissions: HashMap::new(),
+            inbound: HashMap::new(),
+        }
+    }
+}
+
+impl<P, const FIFO: bool, const RETRANSMIT_AFTER: usize> Reliable<P, FIFO, RETRANSMIT_AFTER> {
+    /// The wrapped process.
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// The wrapped process.
+    pub fn inner_mut(&mut self) -> &mut P {
+        &mut self.inner
+    }
+
+    /// Number of sent messages waiting for an acknowledgement.
+    pub fn unacknowledged(&self) -> usize {
+        self.unacked.len()
+    }
+}
+
+impl<P: ProcessHandle, const FIFO: bool, const RETRANSMIT_AFTER: usize>
+    Reliable<P, FIFO, RETRANSMIT_AFTER>
+{
+    // Runs a handler of the wrapped process, moving its sends onto links
+    fn intercept(&mut self, handler: impl FnOnce(&mut P)) {
+        let mark = global::buffered_sends();
+        handler(&mut self.inner);
+        global::take_sends(mark)
+            .into_iter()
+            .for_each(|(destination, message, departure)| {
+                global::resolve_destination(&destination)
+                    .into_iter()
+                    .for_each(|to| self.transmit(to, message.clone(), departure));
+            });
+    }
+
+    fn transmit(&mut self, to: ProcessId, message: Rc<dyn Message>, departure: Jiffies) {
+        let sequence = self.next_sequence.entry(to).or_default();
+        let current = *sequence;
+        *sequence += 1;
+
+        self.unacked.insert((to, current), message.clone());
+        global::push_send(to, Rc::new(LinkMessage::Data(current, message)), departure);
+        self.arm_retransmission(to, current);
+    }
+
+    fn arm_retransmission(&mut self, to: ProcessId, sequence: Sequence) {
+        let timer = schedule_timer_after(Jiffies(RETRANSMIT_AFTER));
+        self.retransmissions.insert(timer, (to, sequence));
+    }
+
+    fn receive(&mut self, from: ProcessId, sequence: Sequence, payload: &Rc<dyn Message>) {
+        send_to(from, LinkMessage::Ack(sequence));
+
+        let inbound = self.inbound.entry(from).or_default();
+        if sequence < inbound.next || inbound.ahead.contains_key(&sequence) {
+            return; // Duplicate
+        }
+
+        if sequence != inbound.next {
+            if FIFO {
+                inbound.ahead.insert(sequence, Some(payload.clone()));
+            } else {
+                inbound.ahead.insert(sequence, None);
+                self.deliver(from, payload.clone());
+            }
+            return;
+        }
+
+        inbound.next += 1;
+        let mut ready = vec![payload.clone()];
+        while let Some(held) = inbound.ahead.remove(&inbound.next) {
+            ready.extend(held);
+            inbound.next += 1;
+        }
+        ready
+            .into_iter()
+            .for_each(|message| self.deliver(from, message));
+    }
+
+    fn deliver(&mut self, from: ProcessId, message: Rc<dyn Message>) {
+        self.intercept(|inner| inner.on_message(from, MessagePtr(message)));
+    }
+}
+
+impl<P: ProcessHandle, const FIFO: bool, const RETRANSMIT_AFTER: usize> ProcessHandle
+    for Reliable<P, FIFO, RETRANSMIT_AFTER>
+{
+    fn start(&mut self) {
+        self.intercept(|inner| inner.start());
+    }
+
+    fn on_message(&mut self, from: ProcessId, message: MessagePtr) {
+        match message.try_as::<LinkMessage>() {
+            None => self.intercept(|inner| inner.on_message(from, message)),
+            Some(link) => match link.as_ref() {
+                LinkMessage::Data(sequence, payload) => self.receive(from, *sequence, payload),
+                LinkMessage::Ack(sequence) => {
+                    self.unacked.remove(&(from, *sequence));
+                }
+            },
+        }
+    }
+
+    fn on_timer(&mut self, id: TimerId) {
+        let Some((to, sequence)) = self.retransmissions.remove(&id) else {
+            self.intercept(|inner| inner.on_timer(id));
+            return;
+        };
+        if let Some(message) = self.unacked.get(&(to, sequence)) {
+            send_to(to, LinkMessage::Data(sequence, message.clone()));
+            self.arm_retransmission(to, sequence);
+        }
+    }
+
+    fn on_storage(&mut self, completion: Completion) {
+        self.intercept(|inner| inner.on_storage(completion));
+    }
+}
@@ -1,4 +1,65 @@
+//! Building blocks for process implementations.
+//!
+//! # Embedding a component
+//!
+//! [`FailureDetector`](failure_detector::FailureDetector),
+//! [`Gossip`](gossip::Gossip), [`Caller`](rpc::Caller) and
+//! [`WorkloadGenerator`](workload::WorkloadGenerator) are not processes of
+//! their own but components living inside one. Keep the component in the
+//! process state, call its `start` from [`ProcessHandle::start`] and forward
+//! incoming messages to its `on_message` and timers to its `on_timer`. A
+//! component only acts on its own messages and timers and ignores everything
+//! else, so the process keeps handling the rest as usual:
+//!
+//! ```rust
+//! use dscale::helpers::failure_detector::{FailureDetector, Mode};
+//! use dscale::*;
+//!
+//! #[derive(Default)]
+//! struct Node {
+//!     detector: Option<FailureDetector>,
+//!     tick: Option<TimerId>,
+//! }
+//!
+//! impl ProcessHandle for Node {
+//!     fn start(&mut self) {
+//!         let mode = Mode::Perfect { timeout: Jiffies(100) };
+//!         let mut detector = FailureDetector::new(list_pool("nodes"), Jiffies(20), mode);
+//!         detector.start();
+//!         self.detector = Some(detector);
+//!         self.tick = Some(schedule_timer_after(Jiffies(50)));
+//!     }
+//!
+//!     fn on_message(&mut self, from: ProcessId, message: MessagePtr) {
+//!         self.detector.as_mut().unwrap().on_message(from, &message);
+//!         // Messages of the process itself
+//!     }
+//!
+//!     fn on_timer(&mut self, id: TimerId) {
+//!         let _events = self.detector.as_mut().unwrap().on_timer(id);
+//!         if Some(id) == self.tick {
+//!             // Timers of the process itself
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! Each component's documentation covers what it returns and what else it
+//! needs from the process.
+//!
+//! [`ProcessHandle::start`]: crate::ProcessHandle::start
+
 pub mod combiner;
+pub mod crypto;
 pub mod debug;
+pub mod failure_detector;
+pub mod gossip;
+pub mod link;
+pub mod quorum;
+pub mod rpc;
+pub mod workload;
 
 pub use combiner::Combiner;
+pub use quorum::{QuorumTracker, Threshold};
+
+pub use crate::debug_process;
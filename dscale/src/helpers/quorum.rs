@@ -0,0 +1,221 @@
+//! Runtime-sized quorum tracking.
+//!
+//! [`QuorumTracker`] counts votes of processes towards one or more
+//! [`Threshold`]s, such as `f + 1` and `2f + 1` of a Byzantine fault-tolerant
+//! protocol. Unlike [`Combiner`](crate::helpers::Combiner) its size is known
+//! only at runtime, each process is counted at most once and votes may carry
+//! weights (stake).
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::ProcessId;
+
+/// Amount of votes a [`QuorumTracker`] waits for.
+///
+/// Thresholds are relative to the total weight `n` of all voters (the number
+/// of voters when votes are not weighted). `f` is the largest number of
+/// faulty voters tolerated by a Byzantine quorum system, `(n - 1) / 3`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Threshold {
+    /// `f + 1`: at least one vote is from a correct voter
+    FPlusOne,
+    /// `2f + 1`: Byzantine quorum, any two of them intersect in a correct voter
+    TwoFPlusOne,
+    /// `n / 2 + 1`: strict majority, any two of them intersect
+    Majority,
+    /// `n`: every voter
+    All,
+    /// Explicit weight
+    AtLeast(u64),
+}
+
+impl Threshold {
+    /// Weight needed to reach the threshold out of `total`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use dscale::helpers::quorum::Threshold;
+    ///
+    /// assert_eq!(Threshold::FPlusOne.weight(7), 3);
+    /// assert_eq!(Threshold::TwoFPlusOne.weight(7), 5);
+    /// assert_eq!(Threshold::Majority.weight(7), 4);
+    /// ```
+    pub fn weight(&self, total: u64) -> u64 {
+        let f = total.saturating_sub(1) / 3;
+        match *self {
+            Threshold::FPlusOne => f + 1,
+            Threshold::TwoFPlusOne => 2 * f + 1,
+            Threshold::Majority => total / 2 + 1,
+            Threshold::All => total,
+            Threshold::AtLeast(weight) => weight,
+        }
+    }
+}
+
+/// Collects votes of distinct processes until thresholds are reached.
+///
+/// Every voter is counted once: repeated votes and votes of processes that
+/// are not voters (for weighted trackers) are ignored. [`vote`](Self::vote)
+/// returns the thresholds crossed by the vote, so each of them is reported
+/// exactly once. Values attached to votes are kept in arrival order.
+///
+/// Protocols that wait for a quorum of matching votes keep one tracker per
+/// voted value.
+///
+/// # Examples
+///
+/// ## Byzantine Broadcast Thresholds
+///
+/// ```rust
+/// use dscale::helpers::quorum::{QuorumTracker, Threshold};
+///
+/// // n = 4, f = 1
+/// let mut echoes: QuorumTracker =
+///     QuorumTracker::new(4, &[Threshold::FPlusOne, Threshold::TwoFPlusOne]);
+///
+/// assert!(echoes.vote(1, ()).is_empty());
+/// assert!(echoes.vote(1, ()).is_empty()); // Duplicate, not counted
+/// assert_eq!(echoes.vote(2, ()), vec![Threshold::FPlusOne]);
+/// assert_eq!(echoes.vote(3, ()), vec![Threshold::TwoFPlusOne]);
+/// assert!(echoes.vote(4, ()).is_empty()); // Both thresholds already reported
+/// assert!(echoes.reached(Threshold::TwoFPlusOne));
+/// ```
+///
+/// ## Stake-Weighted Votes
+///
+/// ```rust
+/// use dscale::helpers::quorum::{QuorumTracker, Threshold};
+///
+/// let mut votes = QuorumTracker::weighted([(1, 50), (2, 30), (3, 20)], &[Threshold::Majority]);
+///
+/// assert!(votes.vote(1, "yes").is_empty()); // 50 of 100 is not a majority
+/// assert!(votes.vote(4, "yes").is_empty()); // Not a voter
+/// assert_eq!(votes.vote(3, "no"), vec![Threshold::Majority]);
+/// assert_eq!(votes.weight(), 70);
+/// assert_eq!(votes.votes(), &[(1, "yes"), (3, "no")]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct QuorumTracker<T = ()> {
+    // None: every process votes with weight 1
+    weights: Option<BTreeMap<ProcessId, u64>>,
+    total: u64,
+    // Threshold, its weight and whether it was reported
+    thresholds: Vec<(Threshold, u64, bool)>,
+    votes: Vec<(ProcessId, T)>,
+    voted: BTreeSet<ProcessId>,
+    weight: u64,
+}
+
+impl<T> QuorumTracker<T> {
+    /// Creates a tracker for `voters` processes voting with weight 1 each.
+    ///
+    /// Any process id is accepted as a voter.
+    ///
+    /// # Arguments
+    ///
+    /// * `voters` - Number of voters, the total weight
+    /// * `thresholds` - Thresholds reported by [`vote`](Self::vote)
+    pub fn new(voters: usize, thresholds: &[Threshold]) -> Self {
+        Self::with_weights(None, voters as u64, thresholds)
+    }
+
+    /// Creates a tracker for processes voting with the given weights.
+    ///
+    /// Only the listed processes can vote.
+    ///
+    /// # Arguments
+    ///
+    /// * `weights` - Weight (stake) of every voter
+    /// * `thresholds` - Thresholds reported by [`vote`](Self::vote), relative to the total weight
+    pub fn weighted(
+        weights: impl IntoIterator<Item = (ProcessId, u64)>,
+        thresholds: &[Threshold],
+    ) -> Self {
+        let weights: BTreeMap<ProcessId, u64> = weights.into_iter().collect();
+        let total = weights.values().sum();
+        Self::with_weights(Some(weights), total, thresholds)
+    }
+
+    fn with_weights(
+        weights: Option<BTreeMap<ProcessId, u64>>,
+        total: u64,
+        thresholds: &[Threshold],
+    ) -> Self {
+        Self {
+            weights,
+            total,
+            thresholds: thresholds
+                .iter()
+                .map(|threshold| (*threshold, threshold.weight(total), false))
+                .collect(),
+            votes: Vec::new(),
+            voted: BTreeSet::new(),
+            weight: 0,
+        }
+    }
+
+    /// Counts the vote of a process.
+    ///
+    /// # Returns
+    ///
+    /// The thresholds reached by this vote, in the order they were given on
+    /// creation. Empty if the vote reached none of them, was a repeated vote
+    /// or came from a process that is not a voter.
+    pub fn vote(&mut self, voter: ProcessId, value: T) -> Vec<Threshold> {
+        if self.voted.contains(&voter) {
+            return Vec::new();
+        }
+        let weight = match &self.weights {
+            None => 1,
+            Some(weights) => match weights.get(&voter) {
+                None => return Vec::new(),
+                Some(weight) => *weight,
+            },
+        };
+
+        self.voted.insert(voter);
+        self.votes.push((voter, value));
+        self.weight += weight;
+
+        let mut crossed = Vec::new();
+        self.thresholds
+            .iter_mut()
+            .filter(|(_, needed, reported)| !*reported && self.weight >= *needed)
+            .for_each(|(threshold, _, reported)| {
+                *reported = true;
+                crossed.push(*threshold);
+            });
+        crossed
+    }
+
+    /// Whether the collected weight reaches the threshold, reported or not.
+    pub fn reached(&self, threshold: Threshold) -> bool {
+        self.weight >= threshold.weight(self.total)
+    }
+
+    /// Whether the process has voted.
+    pub fn has_voted(&self, voter: ProcessId) -> bool {
+        self.voted.contains(&voter)
+    }
+
+    /// Total weight of the votes counted so far.
+    pub fn weight(&self) -> u64 {
+        self.weight
+    }
+
+    /// Total weight of all voters.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Counted votes in arrival order.
+    pub fn votes(&self) -> &[(ProcessId, T)] {
+        &self.votes
+    }
+
+    /// Consumes the tracker, returning the counted votes in arrival order.
+    pub fn into_votes(self) -> Vec<(ProcessId, T)> {
+        self.votes
+    }
+}
@@ -0,0 +1,459 @@
+//! Request/response calls with correlation ids, timeouts and retries.
+//!
+//! Protocols usually wait for replies to their requests: a client waits for
+//! the server, a coordinator waits for a quorum of replicas. This module keeps
+//! the bookkeeping out of the protocol code. A [`Caller`] lives in the calling
+//! process, wraps each request into a [`Request`] envelope carrying a
+//! correlation id and arms a timer for it. The callee answers with [`reply`].
+//! The caller turns incoming replies and its own timers into [`RpcEvent`]s:
+//! either the call got enough replies or it ran out of attempts.
+//!
+//! Everything sits on top of [`send_to`](crate::send_to) and
+//! [`schedule_timer_after`](crate::schedule_timer_after), so calls are subject
+//! to the simulated latency, bandwidth and CPU time like any other message.
+//!
+//! # Examples
+//!
+//! A client calling a server that ignores the first request:
+//!
+//! ```rust
+//! use dscale::helpers::rpc::{self, Caller, Request, RetryPolicy, RpcEvent};
+//! use dscale::*;
+//!
+//! struct Get;
+//! impl Message for Get {}
+//!
+//! struct Value(u64);
+//! impl Message for Value {}
+//!
+//! #[derive(Default)]
+//! struct Client {
+//!     caller: Caller<Get, Value>,
+//! }
+//!
+//! impl ProcessHandle for Client {
+//!     fn start(&mut self) {
+//!         let policy = RetryPolicy::timeout(Jiffies(50)).attempts(3).backoff(2);
+//!         self.caller.call(2, Get, policy);
+//!     }
+//!
+//!     fn on_message(&mut self, from: ProcessId, message: MessagePtr) {
+//!         if let Some(RpcEvent::Completed { replies, .. }) = self.caller.on_message(from, &message) {
+//!             global::anykv::set("value", replies[0].1.0);
+//!         }
+//!     }
+//!
+//!     fn on_timer(&mut self, id: TimerId) {
+//!         if let Some(RpcEvent::TimedOut { .. }) = self.caller.on_timer(id) {
+//!             panic!("Server should answer the retry");
+//!         }
+//!     }
+//! }
+//!
+//! #[derive(Default)]
+//! struct Server {
+//!     requests: usize,
+//! }
+//!
+//! impl ProcessHandle for Server {
+//!     fn start(&mut self) {}
+//!
+//!     fn on_message(&mut self, from: ProcessId, message: MessagePtr) {
+//!         if let Some(request) = message.try_as::<Request<Get>>() {
+//!             self.requests += 1;
+//!             if self.requests > 1 {
+//!                 rpc::reply(from, &request, Value(42));
+//!             }
+//!         }
+//!     }
+//!
+//!     fn on_timer(&mut self, _id: TimerId) {}
+//! }
+//!
+//! let mut simulation = SimulationBuilder::default()
+//!     .add_pool::<Client>("client", 1)
+//!     .add_pool::<Server>("server", 1)
+//!     .latency_topology(&[LatencyDescription::BetweenPools(
+//!         "client",
+//!         "server",
+//!         Distributions::Uniform(Jiffies(5), Jiffies(10)),
+//!     )])
+//!     .time_budget(Jiffies(1_000))
+//!     .build();
+//! simulation.run_until(Jiffies(1_000));
+//!
+//! assert_eq!(global::anykv::get::<u64>("value"), 42);
+//! ```
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    rc::Rc,
+};
+
+use crate::{
+    Message, MessagePtr, ProcessId, TimerId, global_unique_id, schedule_timer_after, send_to,
+    time::Jiffies,
+};
+
+/// Correlation id of a call, unique within a simulation.
+pub type CallId = usize;
+
+/// Envelope of a request sent by a [`Caller`].
+///
+/// Callees match it with [`MessagePtr::try_as`] and answer with [`reply`].
+/// The envelope has the [`virtual_size`](Message::virtual_size) and
+/// [`cpu_cost`](Message::cpu_cost) of its body.
+pub struct Request<Req> {
+    id: CallId,
+    body: Rc<Req>,
+}
+
+impl<Req> Request<Req> {
+    pub fn id(&self) -> CallId {
+        self.id
+    }
+
+    pub fn body(&self) -> &Req {
+        &self.body
+    }
+}
+
+impl<Req: Message> Message for Request<Req> {
+    fn virtual_size(&self) -> usize {
+        self.body.virtual_size()
+    }
+
+    fn cpu_cost(&self) -> Jiffies {
+        self.body.cpu_cost()
+    }
+}
+
+/// Envelope of a reply sent with [`reply`].
+pub struct Response<Resp> {
+    id: CallId,
+    body: Rc<Resp>,
+}
+
+impl<Resp> Response<Resp> {
+    pub fn id(&self) -> CallId {
+        self.id
+    }
+
+    pub fn body(&self) -> &Resp {
+        &self.body
+    }
+}
+
+impl<Resp: Message> Message for Response<Resp> {
+    fn virtual_size(&self) -> usize {
+        self.body.virtual_size()
+    }
+
+    fn cpu_cost(&self) -> Jiffies {
+        self.body.cpu_cost()
+    }
+}
+
+/// Answers a request received from a [`Caller`].
+///
+/// Must be called from within a process context. Replying more than once
+/// is harmless: the caller ignores duplicates and replies to finished calls.
+///
+/// # Arguments
+///
+/// * `to` - The calling process, i.e. the sender of the request
+/// * `request` - The request being answered
+/// * `response` - The reply body
+pub fn reply<Req, Resp: Message>(to: ProcessId, request: &Request<Req>, response: Resp) {
+    send_to(
+        to,
+        Response {
+            id: request.id,
+            body: Rc::new(response),
+        },
+    );
+}
+
+/// Timeouts and retries of a call.
+///
+/// An attempt sends the request to every target that has not replied yet and
+/// waits for `timeout`. Every following attempt waits `backoff` times longer
+/// than the previous one, up to `max_timeout`. After the last attempt the call
+/// times out.
+///
+/// # Examples
+///
+/// ```rust
+/// use dscale::Jiffies;
+/// use dscale::helpers::rpc::RetryPolicy;
+///
+/// // Waits 100, 200, 400 and 500 jiffies
+/// let policy = RetryPolicy::timeout(Jiffies(100))
+///     .attempts(4)
+///     .backoff(2)
+///     .max_timeout(Jiffies(500));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    timeout: Jiffies,
+    attempts: usize,
+    backoff: usize,
+    max_timeout: Jiffies,
+}
+
+impl RetryPolicy {
+    /// A single attempt waiting for `timeout`.
+    pub fn timeout(timeout: Jiffies) -> Self {
+        Self {
+            timeout,
+            attempts: 1,
+            backoff: 1,
+            max_timeout: Jiffies(usize::MAX),
+        }
+    }
+
+    /// Sets the total number of attempts, including the first one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `attempts` is zero.
+    pub fn attempts(mut self, attempts: usize) -> Self {
+        assert!(attempts > 0, "A call needs at least one attempt");
+        self.attempts = attempts;
+        self
+    }
+
+    /// Sets the factor each following timeout is multiplied by.
+    pub fn backoff(mut self, backoff: usize) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Caps the timeout of a single attempt.
+    pub fn max_timeout(mut self, max_timeout: Jiffies) -> Self {
+        self.max_timeout = max_timeout;
+        self
+    }
+
+    // Timeout of the attempt with the given number, starting from 1
+    fn timeout_of(&self, attempt: usize) -> Jiffies {
+        let factor = self
+            .backoff
+            .saturating_pow((attempt - 1).try_into().unwrap_or(u32::MAX));
+        Jiffies(self.timeout.0.saturating_mul(factor)).min(self.max_timeout)
+    }
+}
+
+/// Outcome of a call, returned by [`Caller::on_message`] and [`Caller::on_timer`].
+#[derive(Debug)]
+pub enum RpcEvent<Resp> {
+    /// The call got the number of replies it waited for. Replies are in
+    /// arrival order.
+    Completed {
+        call: CallId,
+        replies: Vec<(ProcessId, Rc<Resp>)>,
+    },
+    /// The last attempt timed out. Carries the replies gathered so far.
+    TimedOut {
+        call: CallId,
+        replies: Vec<(ProcessId, Rc<Resp>)>,
+    },
+}
+
+struct Call<Req, Resp> {
+    request: Rc<Req>,
+    targets: Vec<ProcessId>,
+    needed: usize,
+    replies: Vec<(ProcessId, Rc<Resp>)>,
+    replied: BTreeSet<ProcessId>,
+    policy: RetryPolicy,
+    attempt: usize,
+    timer: TimerId,
+}
+
+// Derived Clone would require Req: Clone and Resp: Clone
+impl<Req, Resp> Clone for Call<Req, Resp> {
+    fn clone(&self) -> Self {
+        Self {
+            request: self.request.clone(),
+            targets: self.targets.clone(),
+            needed: self.needed,
+            replies: self.replies.clone(),
+            replied: self.replied.clone(),
+            policy: self.policy,
+            attempt: self.attempt,
+            timer: self.timer,
+        }
+    }
+}
+
+/// Calls in flight of a single process.
+///
+/// Embedded in the calling process, one per request type, see
+/// [embedding a component](crate::helpers#embedding-a-component). A caller
+/// has no `start`. Both [`on_message`](Self::on_message) and
+/// [`on_timer`](Self::on_timer) return `None` for anything that does not
+/// finish a call.
+///
+/// See the [module documentation](self) for an example.
+pub struct Caller<Req, Resp> {
+    calls: BTreeMap<CallId, Call<Req, Resp>>,
+    // Armed timer of every call in flight
+    timers: BTreeMap<TimerId, CallId>,
+}
+
+impl<Req, Resp> Default for Caller<Req, Resp> {
+    fn default() -> Self {
+        Self {
+            calls: BTreeMap::new(),
+            timers: BTreeMap::new(),
+        }
+    }
+}
+
+impl<Req, Resp> Clone for Caller<Req, Resp> {
+    fn clone(&self) -> Self {
+        Self {
+            calls: self.calls.clone(),
+            timers: self.timers.clone(),
+        }
+    }
+}
+
+impl<Req: Message, Resp: Message> Caller<Req, Resp> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sends a request to a single process and waits for its reply.
+    ///
+    /// Must be called from within a process context.
+    ///
+    /// # Returns
+    ///
+    /// The id the call is reported with in [`RpcEvent`].
+    pub fn call(&mut self, to: ProcessId, request: Req, policy: RetryPolicy) -> CallId {
+        self.call_many([to], 1, request, policy)
+    }
+
+    /// Sends a request to several processes and waits for `needed` of them
+    /// to reply, e.g. for a quorum.
+    ///
+    /// Retries only go to targets that have not replied yet. Must be called
+    /// from within a process context.
+    ///
+    /// # Returns
+    ///
+    /// The id the call is reported with in [`RpcEvent`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `needed` is zero or exceeds the number of targets.
+    pub fn call_many(
+        &mut self,
+        targets: impl IntoIterator<Item = ProcessId>,
+        needed: usize,
+        request: Req,
+        policy: RetryPolicy,
+    ) -> CallId {
+        let targets: Vec<ProcessId> = targets.into_iter().collect();
+        assert!(
+            needed > 0 && needed <= targets.len(),
+            "Cannot wait for {needed} replies from {} targets",
+            targets.len()
+        );
+
+        let id = global_unique_id();
+        self.calls.insert(
+            id,
+            Call {
+                request: Rc::new(request),
+                targets,
+                needed,
+                replies: Vec::new(),
+                replied: BTreeSet::new(),
+                policy,
+                attempt: 0,
+                timer: 0,
+            },
+        );
+        self.attempt(id);
+        id
+    }
+
+    /// Abandons a call: later replies and its timer are ignored.
+    pub fn cancel(&mut self, call: CallId) {
+        if let Some(call) = self.calls.remove(&call) {
+            self.timers.remove(&call.timer);
+        }
+    }
+
+    /// Number of calls still waiting for replies.
+    pub fn in_flight(&self) -> usize {
+        self.calls.len()
+    }
+
+    /// Handles a message, returning [`RpcEvent::Completed`] when it is the
+    /// reply that completes a call.
+    pub fn on_message(&mut self, from: ProcessId, message: &MessagePtr) -> Option<RpcEvent<Resp>> {
+        let response = message.try_as::<Response<Resp>>()?;
+        let call = self.calls.get_mut(&response.id)?;
+        if !call.targets.contains(&from) || !call.replied.insert(from) {
+            return None;
+        }
+        call.replies.push((from, response.body.clone()));
+        if call.replies.len() < call.needed {
+            return None;
+        }
+
+        let call_id = response.id;
+        let call = self
+            .calls
+            .remove(&call_id)
+            .expect("Call should be in flight");
+        self.timers.remove(&call.timer);
+        Some(RpcEvent::Completed {
+            call: call_id,
+            replies: call.replies,
+        })
+    }
+
+    /// Handles a timer, retrying the call it belongs to or returning
+    /// [`RpcEvent::TimedOut`] after its last attempt.
+    pub fn on_timer(&mut self, id: TimerId) -> Option<RpcEvent<Resp>> {
+        let call_id = self.timers.remove(&id)?;
+        let call = self.calls.get(&call_id).expect("Call should be in flight");
+        if call.attempt < call.policy.attempts {
+            self.attempt(call_id);
+            return None;
+        }
+
+        let call = self
+            .calls
+            .remove(&call_id)
+            .expect("Call should be in flight");
+        Some(RpcEvent::TimedOut {
+            call: call_id,
+            replies: call.replies,
+        })
+    }
+
+    fn attempt(&mut self, id: CallId) {
+        let call = self.calls.get_mut(&id).expect("Call should be in flight");
+        call.attempt += 1;
+        call.targets
+            .iter()
+            .filter(|target| !call.replied.contains(target))
+            .for_each(|target| {
+                send_to(
+                    *target,
+                    Request {
+                        id,
+                        body: call.request.clone(),
+                    },
+                );
+            });
+        call.timer = schedule_timer_after(call.policy.timeout_of(call.attempt));
+        self.timers.insert(call.timer, id);
+    }
+}
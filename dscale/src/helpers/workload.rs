@@ -0,0 +1,647 @@
+//! Client workloads: when operations are issued and what they are.
+//!
+//! Benchmarks of storage and consensus systems drive them with clients
+//! issuing operations on keys. A [`Workload`] describes such a client once:
+//!
+//! - Its [`Arrival`] process. Open-loop clients issue operations at a constant
+//!   rate or as a Poisson process regardless of how fast the system answers,
+//!   closed-loop clients keep a fixed number of operations in flight and
+//!   think between them.
+//! - Its operation [`Mix`]: the share of reads, updates, inserts, scans and
+//!   read-modify-writes.
+//! - Its [`KeyDistribution`] over a keyspace of preloaded records: uniform,
+//!   Zipfian, hotspot or skewed towards the latest inserts.
+//!
+//! Presets reproduce the core workloads of YCSB, from [`Workload::ycsb_a`] to
+//! [`Workload::ycsb_f`].
+//!
+//! A [`WorkloadGenerator`] runs a workload inside a client process. It turns
+//! its own timers into operations to issue and measures their latency once
+//! the client reports them complete. Sending the operations and matching
+//! replies to them stays with the client, so the generator fits any system.
+//!
+//! Every process draws from its own random stream seeded with
+//! [`configuration::seed`](crate::global::configuration::seed), so workloads
+//! are reproducible.
+//!
+//! # Examples
+//!
+//! Four clients running YCSB-B against a store at a Poisson rate:
+//!
+//! ```rust
+//! use dscale::helpers::workload::{Arrival, Operation, RequestId, Workload, WorkloadGenerator};
+//! use dscale::*;
+//!
+//! struct Request(RequestId, Operation);
+//! impl Message for Request {}
+//!
+//! struct Reply(RequestId);
+//! impl Message for Reply {}
+//!
+//! struct Client {
+//!     generator: WorkloadGenerator,
+//! }
+//!
+//! impl Default for Client {
+//!     fn default() -> Self {
+//!         let workload = Workload::ycsb_b(1000).arrival(Arrival::Poisson(Jiffies(20)));
+//!         Self { generator: WorkloadGenerator::new(workload) }
+//!     }
+//! }
+//!
+//! impl ProcessHandle for Client {
+//!     fn start(&mut self) {
+//!         self.generator.start();
+//!     }
+//!
+//!     fn on_message(&mut self, _from: ProcessId, message: MessagePtr) {
+//!         let reply = message.as_type::<Reply>();
+//!         self.generator.complete(reply.0);
+//!         global::anykv::modify::<usize>("completed", |completed| *completed += 1);
+//!     }
+//!
+//!     fn on_timer(&mut self, id: TimerId) {
+//!         if let Some((request, operation)) = self.generator.on_timer(id) {
+//!             send_random_from_pool("store", Request(request, operation));
+//!         }
+//!     }
+//! }
+//!
+//! #[derive(Default)]
+//! struct Store;
+//!
+//! impl ProcessHandle for Store {
+//!     fn start(&mut self) {}
+//!
+//!     fn on_message(&mut self, from: ProcessId, message: MessagePtr) {
+//!         let request = message.as_type::<Request>();
+//!         send_to(from, Reply(request.0));
+//!     }
+//!
+//!     fn on_timer(&mut self, _id: TimerId) {}
+//! }
+//!
+//! let mut simulation = SimulationBuilder::default()
+//!     .add_pool::<Client>("clients", 4)
+//!     .add_pool::<Store>("store", 1)
+//!     .latency_topology(&[LatencyDescription::BetweenPools(
+//!         "clients",
+//!         "store",
+//!         Distributions::Uniform(Jiffies(1), Jiffies(5)),
+//!     )])
+//!     .time_budget(Jiffies(10_000))
+//!     .build();
+//!
+//! global::anykv::set::<usize>("completed", 0);
+//! simulation.run();
+//!
+//! // About 10 000 / 20 operations per client
+//! let completed = global::anykv::get::<usize>("completed");
+//! assert!((1800..2200).contains(&completed));
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use rand_distr::{Exp, Zipf};
+
+use crate::{
+    TimerId, global::configuration, global_unique_id, now, schedule_timer_after, time::Jiffies,
+};
+
+/// Key of a record.
+pub type Key = usize;
+
+/// Id of an operation issued by a [`WorkloadGenerator`], unique within a
+/// simulation.
+pub type RequestId = usize;
+
+/// Operation issued by a client.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Operation {
+    Read(Key),
+    Update(Key),
+    /// Creates a new record
+    Insert(Key),
+    /// Reads `length` consecutive records starting at `start`
+    Scan {
+        start: Key,
+        length: usize,
+    },
+    ReadModifyWrite(Key),
+}
+
+impl Operation {
+    /// Key the operation targets, the first one for scans.
+    pub fn key(&self) -> Key {
+        match *self {
+            Operation::Read(key)
+            | Operation::Update(key)
+            | Operation::Insert(key)
+            | Operation::ReadModifyWrite(key) => key,
+            Operation::Scan { start, .. } => start,
+        }
+    }
+
+    /// Whether the operation leaves the records unchanged.
+    pub fn is_read_only(&self) -> bool {
+        matches!(self, Operation::Read(_) | Operation::Scan { .. })
+    }
+}
+
+/// When a client issues operations.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Arrival {
+    /// Open loop: one operation every interval.
+    Constant(Jiffies),
+    /// Open loop: exponentially distributed time between operations with the
+    /// given mean, i.e. a Poisson process.
+    Poisson(Jiffies),
+    /// Closed loop: `concurrency` operations in flight, each completion
+    /// followed by `think_time` before the next operation. The first
+    /// operations are issued `think_time` after start.
+    Closed {
+        concurrency: usize,
+        think_time: Jiffies,
+    },
+}
+
+/// Shares of operation kinds.
+///
+/// Shares are relative weights and need not sum to 1.
+///
+/// # Examples
+///
+/// ```rust
+/// use dscale::helpers::workload::Mix;
+///
+/// let read_mostly = Mix { read: 0.9, update: 0.1, ..Mix::default() };
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Mix {
+    pub read: f64,
+    pub update: f64,
+    pub insert: f64,
+    pub scan: f64,
+    pub read_modify_write: f64,
+}
+
+impl Mix {
+    fn total(&self) -> f64 {
+        self.read + self.update + self.insert + self.scan + self.read_modify_write
+    }
+}
+
+/// Which keys operations target.
+///
+/// Reads, updates, scans and read-modify-writes target the preloaded records
+/// `0..record_count`; only [`KeyDistribution::Latest`] also targets records
+/// inserted since.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KeyDistribution {
+    /// Every record equally likely.
+    Uniform,
+    /// Zipfian with the given exponent: key 0 is the most popular, key 1 the
+    /// second most popular and so on. YCSB uses 0.99.
+    Zipfian(f64),
+    /// A fraction `hot_keys` of the records (the lowest keys) receives a
+    /// fraction `hot_operations` of the operations, uniformly within the hot
+    /// and the cold set.
+    Hotspot { hot_keys: f64, hot_operations: f64 },
+    /// Zipfian with the given exponent over the records ordered from the
+    /// most recently inserted by this client to the oldest.
+    Latest(f64),
+}
+
+/// Description of a client workload, see the [module documentation](self).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Workload {
+    arrival: Arrival,
+    mix: Mix,
+    keys: KeyDistribution,
+    record_count: usize,
+    max_scan_length: usize,
+    operations: Option<usize>,
+}
+
+impl Workload {
+    /// Creates a workload with scans of up to 100 records and no limit on the
+    /// number of operations.
+    ///
+    /// # Arguments
+    ///
+    /// * `arrival` - When operations are issued
+    /// * `mix` - Shares of operation kinds
+    /// * `keys` - Which keys operations target
+    /// * `record_count` - Number of preloaded records, keyed `0..record_count`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mix has no positive share, if there are no records, if
+    /// an open-loop interval is zero, if a closed loop has no concurrency or
+    /// if the key distribution has invalid parameters.
+    pub fn new(arrival: Arrival, mix: Mix, keys: KeyDistribution, record_count: usize) -> Self {
+        let shares = [
+            mix.read,
+            mix.update,
+            mix.insert,
+            mix.scan,
+            mix.read_modify_write,
+        ];
+        assert!(
+            shares.iter().all(|share| *share >= 0.0) && mix.total() > 0.0,
+            "Operation shares should be non-negative with a positive sum"
+        );
+        assert!(record_count > 0, "Workload should have records");
+        match arrival {
+            Arrival::Constant(interval) | Arrival::Poisson(interval) => {
+                assert!(interval > Jiffies(0), "Arrival interval should be positive")
+            }
+            Arrival::Closed { concurrency, .. } => {
+                assert!(
+                    concurrency > 0,
+                    "Closed loop should have operations in flight"
+                )
+            }
+        }
+        match keys {
+            KeyDistribution::Uniform => {}
+            KeyDistribution::Zipfian(exponent) | KeyDistribution::Latest(exponent) => {
+                assert!(exponent >= 0.0, "Zipfian exponent should be non-negative")
+            }
+            KeyDistribution::Hotspot {
+                hot_keys,
+                hot_operations,
+            } => assert!(
+                (0.0..=1.0).contains(&hot_keys) && (0.0..=1.0).contains(&hot_operations),
+                "Hotspot fractions should be within [0, 1]"
+            ),
+        }
+        Self {
+            arrival,
+            mix,
+            keys,
+            record_count,
+            max_scan_length: 100,
+            operations: None,
+        }
+    }
+
+    /// Replaces the arrival process, e.g. of a preset.
+    pub fn arrival(mut self, arrival: Arrival) -> Self {
+        self.arrival = arrival;
+        self
+    }
+
+    /// Sets the maximum length of scans, drawn uniformly from
+    /// `1..=max_scan_length`.
+    pub fn max_scan_length(mut self, length: usize) -> Self {
+        assert!(length > 0, "Scans should read at least one record");
+        self.max_scan_length = length;
+        self
+    }
+
+    /// Stops issuing operations after `operations` of them.
+    ///
+    /// Note that a simulation whose clients all stopped may run out of events
+    /// before its time budget, which [`Simulation::run`](crate::Simulation::run)
+    /// reports as a deadlock.
+    pub fn operations(mut self, operations: usize) -> Self {
+        self.operations = Some(operations);
+        self
+    }
+
+    // YCSB clients are closed-loop threads without think time
+    fn ycsb(mix: Mix, keys: KeyDistribution, record_count: usize) -> Self {
+        let arrival = Arrival::Closed {
+            concurrency: 1,
+            think_time: Jiffies(0),
+        };
+        Self::new(arrival, mix, keys, record_count)
+    }
+
+    /// YCSB-A, update heavy: 50% reads, 50% updates, Zipfian keys.
+    ///
+    /// YCSB presets run a closed loop with a single operation in flight and
+    /// no think time; replace it with [`arrival`](Self::arrival).
+    pub fn ycsb_a(record_count: usize) -> Self {
+        let mix = Mix {
+            read: 0.5,
+            update: 0.5,
+            ..Mix::default()
+        };
+        Self::ycsb(mix, KeyDistribution::Zipfian(0.99), record_count)
+    }
+
+    /// YCSB-B, read mostly: 95% reads, 5% updates, Zipfian keys.
+    pub fn ycsb_b(record_count: usize) -> Self {
+        let mix = Mix {
+            read: 0.95,
+            update: 0.05,
+            ..Mix::default()
+        };
+        Self::ycsb(mix, KeyDistribution::Zipfian(0.99), record_count)
+    }
+
+    /// YCSB-C, read only: 100% reads, Zipfian keys.
+    pub fn ycsb_c(record_count: usize) -> Self {
+        let mix = Mix {
+            read: 1.0,
+            ..Mix::default()
+        };
+        Self::ycsb(mix, KeyDistribution::Zipfian(0.99), record_count)
+    }
+
+    /// YCSB-D, read latest: 95% reads, 5% inserts, reads skewed towards the
+    /// latest inserts.
+    pub fn ycsb_d(record_count: usize) -> Self {
+        let mix = Mix {
+            read: 0.95,
+            insert: 0.05,
+            ..Mix::default()
+        };
+        Self::ycsb(mix, KeyDistribution::Latest(0.99), record_count)
+    }
+
+    /// YCSB-E, short ranges: 95% scans of up to 100 records, 5% inserts,
+    /// Zipfian scan starts.
+    pub fn ycsb_e(record_count: usize) -> Self {
+        let mix = Mix {
+            scan: 0.95,
+            insert: 0.05,
+            ..Mix::default()
+        };
+        Self::ycsb(mix, KeyDistribution::Zipfian(0.99), record_count)
+    }
+
+    /// YCSB-F, read-modify-write: 50% reads, 50% read-modify-writes, Zipfian
+    /// keys.
+    pub fn ycsb_f(record_count: usize) -> Self {
+        let mix = Mix {
+            read: 0.5,
+            read_modify_write: 0.5,
+            ..Mix::default()
+        };
+        Self::ycsb(mix, KeyDistribution::Zipfian(0.99), record_count)
+    }
+}
+
+/// Counters and latencies of a single [`WorkloadGenerator`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WorkloadStats {
+    pub issued: usize,
+    pub completed: usize,
+    /// Latencies of completed operations in completion order
+    pub latencies: Vec<Jiffies>,
+}
+
+impl WorkloadStats {
+    /// Mean latency of completed operations, `None` before the first
+    /// completion.
+    pub fn mean_latency(&self) -> Option<f64> {
+        let total: usize = self.latencies.iter().map(|latency| latency.0).sum();
+        (!self.latencies.is_empty()).then(|| total as f64 / self.latencies.len() as f64)
+    }
+
+    /// Latency below which a fraction `p` of the completed operations
+    /// finished (nearest rank), `None` before the first completion.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use dscale::Jiffies;
+    /// use dscale::helpers::workload::WorkloadStats;
+    ///
+    /// let stats = WorkloadStats {
+    ///     latencies: (1..=100).map(Jiffies).collect(),
+    ///     ..WorkloadStats::default()
+    /// };
+    /// assert_eq!(stats.percentile(0.5), Some(Jiffies(50)));
+    /// assert_eq!(stats.percentile(0.99), Some(Jiffies(99)));
+    /// assert_eq!(stats.percentile(1.0), Some(Jiffies(100)));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `p` is not within `[0, 1]`.
+    pub fn percentile(&self, p: f64) -> Option<Jiffies> {
+        assert!(
+            (0.0..=1.0).contains(&p),
+            "Percentile should be within [0, 1]"
+        );
+        let mut sorted = self.latencies.clone();
+        sorted.sort_unstable();
+        let rank = (p * sorted.len() as f64).ceil() as usize;
+        sorted.get(rank.saturating_sub(1)).copied()
+    }
+}
+
+/// Workload state of a single client process.
+///
+/// Embedded in the client process, see
+/// [embedding a component](crate::helpers#embedding-a-component). The
+/// generator sends no messages, so only timers are forwarded, and completed
+/// operations are reported with [`complete`](Self::complete).
+///
+/// See the [module documentation](self) for an example.
+#[derive(Clone)]
+pub struct WorkloadGenerator {
+    workload: Workload,
+    rng: Option<StdRng>,
+    // Inserted by this client, oldest first
+    inserted: Vec<Key>,
+    // Issue time of operations in flight
+    pending: BTreeMap<RequestId, Jiffies>,
+    timers: BTreeSet<TimerId>,
+    stats: WorkloadStats,
+}
+
+impl WorkloadGenerator {
+    pub fn new(workload: Workload) -> Self {
+        Self {
+            workload,
+            rng: None,
+            inserted: Vec::new(),
+            pending: BTreeMap::new(),
+            timers: BTreeSet::new(),
+            stats: WorkloadStats::default(),
+        }
+    }
+
+    /// Schedules the first operations.
+    ///
+    /// Must be called from within a process context.
+    pub fn start(&mut self) {
+        self.rng = Some(StdRng::seed_from_u64(configuration::seed()));
+        match self.workload.arrival {
+            Arrival::Closed {
+                concurrency,
+                think_time,
+            } => (0..concurrency).for_each(|_| self.schedule(think_time)),
+            Arrival::Constant(_) | Arrival::Poisson(_) => {
+                let first = self.next_arrival();
+                self.schedule(first);
+            }
+        }
+    }
+
+    /// Handles a timer.
+    ///
+    /// Must be called from within a process context.
+    ///
+    /// # Returns
+    ///
+    /// The operation to issue now together with the id to report its
+    /// completion with, `None` for foreign timers or once the operation limit
+    /// is reached.
+    pub fn on_timer(&mut self, id: TimerId) -> Option<(RequestId, Operation)> {
+        if !self.timers.remove(&id) {
+            return None;
+        }
+        if self
+            .workload
+            .operations
+            .is_some_and(|limit| self.stats.issued >= limit)
+        {
+            return None;
+        }
+        if matches!(
+            self.workload.arrival,
+            Arrival::Constant(_) | Arrival::Poisson(_)
+        ) {
+            let next = self.next_arrival();
+            self.schedule(next);
+        }
+
+        let request = global_unique_id();
+        let operation = self.next_operation();
+        self.pending.insert(request, now());
+        self.stats.issued += 1;
+        Some((request, operation))
+    }
+
+    /// Reports an operation as complete and, in a closed loop, schedules the
+    /// next one after the think time.
+    ///
+    /// Must be called from within a process context.
+    ///
+    /// # Returns
+    ///
+    /// Latency of the operation, `None` if it is not in flight, e.g. when
+    /// completed twice.
+    pub fn complete(&mut self, request: RequestId) -> Option<Jiffies> {
+        let issued = self.pending.remove(&request)?;
+        let latency = now().saturating_sub(issued);
+        self.stats.completed += 1;
+        self.stats.latencies.push(latency);
+        if let Arrival::Closed { think_time, .. } = self.workload.arrival {
+            self.schedule(think_time);
+        }
+        Some(latency)
+    }
+
+    /// Number of issued operations not completed yet.
+    pub fn in_flight(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn stats(&self) -> &WorkloadStats {
+        &self.stats
+    }
+
+    pub fn workload(&self) -> &Workload {
+        &self.workload
+    }
+
+    fn schedule(&mut self, after: Jiffies) {
+        self.timers.insert(schedule_timer_after(after));
+    }
+
+    fn rng(&mut self) -> &mut StdRng {
+        self.rng
+            .as_mut()
+            .expect("Workload generator is not started")
+    }
+
+    fn next_arrival(&mut self) -> Jiffies {
+        match self.workload.arrival {
+            Arrival::Constant(interval) => interval,
+            Arrival::Poisson(mean) => {
+                let distr = Exp::new(1.0 / mean.0 as f64).expect("Invalid mean interval");
+                Jiffies(self.rng().sample(distr).round() as usize)
+            }
+            Arrival::Closed { think_time, .. } => think_time,
+        }
+    }
+
+    fn next_operation(&mut self) -> Operation {
+        let mix = self.workload.mix;
+        let shares = [
+            mix.read,
+            mix.update,
+            mix.insert,
+            mix.scan,
+            mix.read_modify_write,
+        ];
+        let mut choice = self.rng().random_range(0.0..mix.total());
+        // Falls back to the last kind with a share if rounding overshoots
+        let kind = (0..shares.len())
+            .filter(|kind| shares[*kind] > 0.0)
+            .find(|kind| {
+                choice -= shares[*kind];
+                choice < 0.0
+            })
+            .or_else(|| shares.iter().rposition(|share| *share > 0.0))
+            .expect("Mix has a positive share");
+        match kind {
+            0 => Operation::Read(self.next_key()),
+            1 => Operation::Update(self.next_key()),
+            2 => {
+                // Unique across clients
+                let key = self.workload.record_count + global_unique_id();
+                self.inserted.push(key);
+                Operation::Insert(key)
+            }
+            3 => {
+                let start = self.next_key();
+                let longest = self.workload.max_scan_length;
+                let length = self.rng().random_range(1..=longest);
+                Operation::Scan { start, length }
+            }
+            _ => Operation::ReadModifyWrite(self.next_key()),
+        }
+    }
+
+    fn next_key(&mut self) -> Key {
+        let records = self.workload.record_count;
+        match self.workload.keys {
+            KeyDistribution::Uniform => self.rng().random_range(0..records),
+            KeyDistribution::Zipfian(exponent) => self.zipf_rank(records, exponent),
+            KeyDistribution::Hotspot {
+                hot_keys,
+                hot_operations,
+            } => {
+                let hot = ((hot_keys * records as f64).ceil() as usize).clamp(1, records);
+                if hot == records || self.rng().random_bool(hot_operations) {
+                    self.rng().random_range(0..hot)
+                } else {
+                    self.rng().random_range(hot..records)
+                }
+            }
+            KeyDistribution::Latest(exponent) => {
+                let rank = self.zipf_rank(records + self.inserted.len(), exponent);
+                match self.inserted.len().checked_sub(rank + 1) {
+                    Some(index) => self.inserted[index],
+                    None => records - 1 - (rank - self.inserted.len()),
+                }
+            }
+        }
+    }
+
+    // Zero-based rank in 0..n, 0 the most popular
+    fn zipf_rank(&mut self, n: usize, exponent: f64) -> usize {
+        let distr = Zipf::new(n as f64, exponent).expect("Invalid Zipfian parameters");
+        let rank = self.rng().sample(distr) as usize;
+        rank.clamp(1, n) - 1
+    }
+}
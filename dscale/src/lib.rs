@@ -1,6 +1,8 @@
 mod actor;
 mod alloc;
 mod communication;
+pub mod explore;
+pub mod fuzz;
 pub mod global;
 pub mod helpers;
 mod network;
@@ -8,10 +10,16 @@ mod nursery;
 mod process;
 mod progress;
 mod random;
+#[cfg(feature = "scenario")]
+pub mod scenario;
 mod simulation;
 mod simulation_builder;
+mod snapshot;
+pub mod stats;
+pub mod storage;
 pub mod time;
 mod topology;
+pub mod trace;
 
 pub use communication::MessagePtr;
 pub use communication::{Destination, Message};
@@ -21,17 +29,24 @@ pub use process::ProcessId;
 
 pub use simulation::Simulation;
 pub use simulation_builder::SimulationBuilder;
+pub use snapshot::Snapshot;
 
 pub use global::broadcast;
 pub use global::broadcast_within_pool;
 pub use global::choose_from_pool;
+pub use global::consume_cpu;
 pub use global::global_unique_id;
 pub use global::list_pool;
+pub use global::local_now;
 pub use global::now;
 pub use global::rank;
 pub use global::schedule_timer_after;
 pub use global::send_random_from_pool;
 pub use global::send_to;
+pub use global::set_clock_skew;
+
+pub use global::ClockSkew;
+pub use global::TieBreaking;
 
 pub use network::BandwidthDescription;
 
@@ -5,12 +5,14 @@
 //! Bandwidth constraints are applied per-process to model individual network
 //! interface limitations.
 
-use std::collections::BinaryHeap;
+use std::{collections::BinaryHeap, rc::Rc};
 
 use log::debug;
 
 use crate::{
+    Message, ProcessId,
     communication::{RoutedMessage, TimePriorityMessageQueue},
+    global::{self, TieKey},
     network::LatencyQueue,
     now,
     time::Jiffies,
@@ -240,8 +242,52 @@ pub enum BandwidthDescription {
     /// // - LargeMessage with 2500 bytes: takes 3 jiffies (⌈2500/1000⌉)
     /// ```
     Bounded(usize), // Bytes per Jiffy
+
+    /// Limited bandwidth in megabits (10^6 bits) per second.
+    ///
+    /// Converted to bytes per jiffy with the length of a jiffy declared by
+    /// [`SimulationBuilder::jiffy`], and otherwise behaves like
+    /// [`Bounded`](Self::Bounded). Replaces hand-written conversions such as
+    /// `mbps * 1_000_000 / 8 / 1000` for millisecond jiffies.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    ///
+    /// use dscale::{BandwidthDescription, SimulationBuilder};
+    ///
+    /// let simulation = SimulationBuilder::default()
+    ///     .jiffy(Duration::from_millis(1))
+    ///     .nic_bandwidth(BandwidthDescription::Mbps(100)) // 12_500 bytes per jiffy
+    ///     .build();
+    /// ```
+    ///
+    /// [`SimulationBuilder::jiffy`]: crate::SimulationBuilder::jiffy
+    Mbps(usize),
+
+    /// Limited bandwidth in gigabits (10^9 bits) per second, see
+    /// [`Mbps`](Self::Mbps).
+    Gbps(usize),
+}
+
+impl BandwidthDescription {
+    // None if unbounded. Rates in bits per second are converted with the
+    // length of a jiffy of the current simulation.
+    pub(crate) fn bytes_per_jiffy(&self) -> Option<usize> {
+        let bits_per_second = match *self {
+            BandwidthDescription::Unbounded => return None,
+            BandwidthDescription::Bounded(bytes) => return Some(bytes),
+            BandwidthDescription::Mbps(mbps) => mbps as u128 * 1_000_000,
+            BandwidthDescription::Gbps(gbps) => gbps as u128 * 1_000_000_000,
+        };
+        let bytes = bits_per_second * global::jiffy().as_nanos() / (8 * 1_000_000_000);
+        // A link slower than a byte per jiffy still makes progress
+        Some((bytes as usize).max(1))
+    }
 }
 
+#[derive(Clone)]
 pub(crate) struct BandwidthQueue {
     bandwidth: usize,
     global_queue: LatencyQueue,
@@ -255,10 +301,7 @@ impl BandwidthQueue {
         proc_num: usize,
         global_queue: LatencyQueue,
     ) -> Self {
-        let bandwidth = match bandwidth_type {
-            BandwidthDescription::Unbounded => usize::MAX,
-            BandwidthDescription::Bounded(bound) => bound,
-        };
+        let bandwidth = bandwidth_type.bytes_per_jiffy().unwrap_or(usize::MAX);
 
         Self {
             bandwidth,
@@ -268,9 +311,17 @@ impl BandwidthQueue {
         }
     }
 
-    pub(crate) fn push(&mut self, message: RoutedMessage) {
-        debug!("Submitted message with base time: {}", message.arrival_time);
-        self.global_queue.push(message);
+    pub(crate) fn push(
+        &mut self,
+        source: ProcessId,
+        message: Rc<dyn Message>,
+        sent_at: Jiffies,
+        base_arrival_time: Jiffies,
+        targets: impl Iterator<Item = (TieKey, ProcessId)>,
+    ) {
+        debug!("Submitted message with base time: {base_arrival_time}");
+        self.global_queue
+            .push(source, message, sent_at, base_arrival_time, targets);
     }
 
     pub(crate) fn pop(&mut self) -> Option<RoutedMessage> {
@@ -282,7 +333,7 @@ impl BandwidthQueue {
             (Some(_), None) => self.deliver_from_latency_queue(),
             (None, Some(_)) => self.deliver_from_buffer(),
             (Some(l_message), Some(b_message)) => {
-                if l_message.arrival_time <= b_message.0.arrival_time {
+                if l_message <= b_message.0.order() {
                     self.deliver_from_latency_queue()
                 } else {
                     self.deliver_from_buffer()
@@ -291,21 +342,15 @@ impl BandwidthQueue {
         }
     }
 
-    pub(crate) fn peek_closest(&self) -> Option<Jiffies> {
+    pub(crate) fn peek_closest(&self) -> Option<(Jiffies, TieKey)> {
         let closest_arriving_message = self.global_queue.peek();
         let closest_squeezing_message = self.merged_fifo_buffers.peek();
 
         match (closest_arriving_message, closest_squeezing_message) {
             (None, None) => None,
-            (Some(m), None) => Some(m.arrival_time),
-            (None, Some(m)) => Some(m.0.arrival_time),
-            (Some(l_message), Some(b_message)) => {
-                if l_message.arrival_time <= b_message.0.arrival_time {
-                    Some(l_message.arrival_time)
-                } else {
-                    Some(b_message.0.arrival_time)
-                }
-            }
+            (Some(m), None) => Some(m),
+            (None, Some(m)) => Some(m.0.order()),
+            (Some(l_message), Some(b_message)) => Some(l_message.min(b_message.0.order())),
         }
     }
 }
@@ -0,0 +1,41 @@
+use std::collections::HashMap;
+
+use log::debug;
+
+use crate::{ProcessId, communication::ProcessStep};
+
+// In-flight messages whose delivery order is decided from outside (see explore module).
+// Every message is tagged with its ordinal on the (source, dest) link, which stays
+// stable across replays of the same schedule prefix.
+#[derive(Clone, Default)]
+pub(crate) struct ControlledQueue {
+    in_flight: Vec<(usize, ProcessStep)>,
+    sent: HashMap<(ProcessId, ProcessId), usize>,
+}
+
+impl ControlledQueue {
+    pub(crate) fn push(&mut self, step: ProcessStep) {
+        let seq = self.sent.entry((step.source, step.dest)).or_default();
+        debug!(
+            "Holding message P{} -> P{} #{} for controlled delivery",
+            step.source, step.dest, seq
+        );
+        self.in_flight.push((*seq, step));
+        *seq += 1;
+    }
+
+    pub(crate) fn in_flight(&self) -> impl Iterator<Item = (ProcessId, ProcessId, usize)> + '_ {
+        self.in_flight
+            .iter()
+            .map(|(seq, step)| (step.source, step.dest, *seq))
+    }
+
+    pub(crate) fn take(&mut self, from: ProcessId, to: ProcessId, seq: usize) -> ProcessStep {
+        let idx = self
+            .in_flight
+            .iter()
+            .position(|(s, step)| *s == seq && step.source == from && step.dest == to)
+            .expect("No such message in flight");
+        self.in_flight.swap_remove(idx).1
+    }
+}
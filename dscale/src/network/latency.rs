@@ -1,16 +1,61 @@
+use std::cmp::Reverse;
 use std::collections::BinaryHeap;
+use std::collections::binary_heap::PeekMut;
 use std::rc::Rc;
 
 use log::debug;
 
-use crate::communication::{RoutedMessage, TimePriorityMessageQueue};
+use crate::Message;
+use crate::ProcessId;
+use crate::communication::{ProcessStep, RoutedMessage};
+use crate::global::TieKey;
 use crate::random::Randomizer;
+use crate::time::Jiffies;
 use crate::topology::Topology;
 
+// A message on its way to all of its targets, stored once instead of once per target.
+// Latencies are sampled for every target at submission (same draws as per-target entries),
+// the schedule is kept sorted with the closest arrival last and expanded one target at a time.
+#[derive(Clone)]
+struct InFlight {
+    source: ProcessId,
+    message: Rc<dyn Message>,
+    sent_at: Jiffies,
+    schedule: Vec<(Jiffies, TieKey, ProcessId)>,
+}
+
+impl InFlight {
+    fn order(&self) -> (Jiffies, TieKey) {
+        let (arrival_time, tie, _) = *self.schedule.last().expect("Schedule should not be empty");
+        (arrival_time, tie)
+    }
+}
+
+impl PartialEq for InFlight {
+    fn eq(&self, other: &Self) -> bool {
+        self.order().eq(&other.order())
+    }
+}
+
+impl Eq for InFlight {}
+
+impl PartialOrd for InFlight {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for InFlight {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.order().cmp(&other.order())
+    }
+}
+
+#[derive(Clone)]
 pub(crate) struct LatencyQueue {
     topology: Rc<Topology>,
     randomizer: Randomizer,
-    queue: TimePriorityMessageQueue,
+    queue: BinaryHeap<Reverse<InFlight>>,
 }
 impl LatencyQueue {
     pub(crate) fn new(randomizer: Randomizer, topology: Rc<Topology>) -> Self {
@@ -21,27 +66,65 @@ impl LatencyQueue {
         }
     }
 
-    pub(crate) fn push(&mut self, mut message: RoutedMessage) {
-        debug!(
-            "Arrival time before adding latency: {}",
-            message.arrival_time
-        );
-        message.arrival_time += self.randomizer.random_usize(
-            self.topology
-                .get_distribution(message.step.source, message.step.dest),
-        );
-        debug!(
-            "Arrival time after adding random latency: {}",
-            message.arrival_time
-        );
-        self.queue.push(std::cmp::Reverse(message));
+    // All targets share the base arrival time (departure without any latency)
+    pub(crate) fn push(
+        &mut self,
+        source: ProcessId,
+        message: Rc<dyn Message>,
+        sent_at: Jiffies,
+        base_arrival_time: Jiffies,
+        targets: impl Iterator<Item = (TieKey, ProcessId)>,
+    ) {
+        debug!("Arrival time before adding latency: {base_arrival_time}");
+        let mut schedule: Vec<(Jiffies, TieKey, ProcessId)> = targets
+            .map(|(tie, dest)| {
+                let arrival_time = base_arrival_time
+                    + Jiffies(
+                        self.randomizer
+                            .random_usize(self.topology.get_distribution(source, dest)),
+                    );
+                debug!("Arrival time at P{dest} after adding random latency: {arrival_time}");
+                (arrival_time, tie, dest)
+            })
+            .collect();
+
+        if schedule.is_empty() {
+            return;
+        }
+
+        schedule.sort_unstable_by_key(|(arrival_time, tie, _)| Reverse((*arrival_time, *tie)));
+        self.queue.push(Reverse(InFlight {
+            source,
+            message,
+            sent_at,
+            schedule,
+        }));
     }
 
     pub(crate) fn pop(&mut self) -> Option<RoutedMessage> {
-        Some(self.queue.pop()?.0)
+        // Expanding a target only moves the entry down the heap under its next arrival,
+        // a single sift instead of a pop and a push
+        let mut closest = self.queue.peek_mut()?;
+        let in_flight = &mut closest.0;
+        let (arrival_time, tie, dest) = in_flight.schedule.pop()?;
+        let routed_message = RoutedMessage {
+            arrival_time,
+            tie,
+            step: ProcessStep {
+                source: in_flight.source,
+                dest,
+                message: in_flight.message.clone(),
+                sent_at: in_flight.sent_at,
+            },
+        };
+
+        if in_flight.schedule.is_empty() {
+            PeekMut::pop(closest);
+        }
+        Some(routed_message)
     }
 
-    pub(crate) fn peek(&self) -> Option<&RoutedMessage> {
-        Some(&self.queue.peek()?.0)
+    pub(crate) fn peek(&self) -> Option<(Jiffies, TieKey)> {
+        Some(self.queue.peek()?.0.order())
     }
 }
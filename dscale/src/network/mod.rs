@@ -1,11 +1,15 @@
 mod bandwidth;
+mod controlled;
 mod latency;
 
 use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
 use std::rc::Rc;
 
 pub use bandwidth::BandwidthDescription;
 pub(crate) use bandwidth::BandwidthQueue;
+pub(crate) use controlled::ControlledQueue;
 pub(crate) use latency::LatencyQueue;
 use log::debug;
 
@@ -17,12 +21,17 @@ use crate::actor::EventSubmitter;
 use crate::actor::SimulationActor;
 use crate::communication::DScaleMessage;
 use crate::communication::ProcessStep;
-use crate::communication::RoutedMessage;
+use crate::communication::TimePriorityMessageQueue;
+use crate::global;
+use crate::global::TieKey;
 use crate::global::configuration;
+use crate::global::cpu;
 use crate::now;
 use crate::nursery::Nursery;
 use crate::random::Randomizer;
 use crate::random::Seed;
+use crate::stats::NetworkCounters;
+use crate::stats::Statistics;
 use crate::time::Jiffies;
 use crate::topology::Topology;
 
@@ -31,8 +40,12 @@ pub(crate) type NetworkActor = Rc<RefCell<Network>>;
 pub(crate) struct Network {
     seed: Seed,
     bandwidth_queue: BandwidthQueue,
+    // Received messages waiting for their busy destination, see global::cpu
+    backlog: TimePriorityMessageQueue,
+    controlled: Option<ControlledQueue>,
     topology: Rc<Topology>,
     nursery: Rc<Nursery>,
+    counters: NetworkCounters,
 }
 
 impl Network {
@@ -41,6 +54,7 @@ impl Network {
         message: Rc<dyn Message>,
         source: ProcessId,
         destination: Destination,
+        departure: Jiffies,
     ) {
         let targets = match destination {
             Destination::Broadcast => self.nursery.keys().copied().collect::<Vec<ProcessId>>(),
@@ -52,17 +66,36 @@ impl Network {
 
         debug!("Submitting message from {source}, targets of the message: {targets:?}",);
 
-        targets.into_iter().for_each(|target| {
-            let routed_message = RoutedMessage {
-                arrival_time: now() + Jiffies(1), // Without any latency message will arrive on next timepoint;
-                step: ProcessStep {
+        self.counters.sent(
+            &self.topology,
+            message.name(),
+            message.virtual_size(),
+            source,
+            &targets,
+        );
+
+        if let Some(controlled) = self.controlled.as_mut() {
+            targets.into_iter().for_each(|target| {
+                controlled.push(ProcessStep {
                     source,
                     dest: target,
                     message: message.clone(),
-                },
-            };
-            self.bandwidth_queue.push(routed_message);
-        });
+                    sent_at: now() + departure,
+                });
+            });
+            return;
+        }
+
+        // A broadcast stays a single entry in the latency queue, see LatencyQueue
+        self.bandwidth_queue.push(
+            source,
+            message,
+            now() + departure,
+            now() + departure + Jiffies(1), // Without any latency message will arrive on next timepoint
+            targets
+                .into_iter()
+                .map(|target| (global::next_tie_key(), target)),
+        );
     }
 
     fn execute_process_step(&mut self, step: ProcessStep) {
@@ -70,10 +103,11 @@ impl Network {
         let dest = step.dest;
         let message = step.message;
 
+        self.counters.delivered(dest, message.virtual_size());
         self.nursery.deliver(
             source,
             dest,
-            DScaleMessage::NetworkMessage(MessagePtr(message)),
+            DScaleMessage::NetworkMessage(MessagePtr(message), step.sent_at),
         );
     }
 }
@@ -92,10 +126,52 @@ impl Network {
                 nursery.size(),
                 LatencyQueue::new(Randomizer::new(seed), topology.clone()),
             ),
+            backlog: BinaryHeap::new(),
+            controlled: None,
+            counters: NetworkCounters::new(&topology, nursery.size()),
             topology,
             nursery,
         }
     }
+
+    // Same in-flight messages and latency stream, delivering to the processes of a forked nursery
+    pub(crate) fn fork(&self, nursery: Rc<Nursery>) -> Self {
+        Self {
+            seed: self.seed,
+            bandwidth_queue: self.bandwidth_queue.clone(),
+            backlog: self.backlog.clone(),
+            controlled: self.controlled.clone(),
+            topology: self.topology.clone(),
+            nursery,
+            counters: self.counters.clone(),
+        }
+    }
+
+    pub(crate) fn report(&self, statistics: &mut Statistics) {
+        self.counters.report(&self.topology, statistics);
+    }
+
+    // Hand delivery order over to the caller: messages are no longer delayed
+    // by latency or bandwidth and wait in flight until explicitly delivered.
+    pub(crate) fn take_control(&mut self) {
+        self.controlled = Some(ControlledQueue::default());
+    }
+
+    pub(crate) fn in_flight(&self) -> Vec<(ProcessId, ProcessId, usize)> {
+        self.controlled
+            .as_ref()
+            .map(|controlled| controlled.in_flight().collect())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn deliver_controlled(&mut self, from: ProcessId, to: ProcessId, seq: usize) {
+        let step = self
+            .controlled
+            .as_mut()
+            .expect("Network is not under control")
+            .take(from, to, seq);
+        self.execute_process_step(step);
+    }
 }
 
 impl SimulationActor for Network {
@@ -106,28 +182,57 @@ impl SimulationActor for Network {
         });
     }
 
-    fn step(&mut self) {
-        let next_event = self.bandwidth_queue.pop();
+    fn step(&mut self) -> bool {
+        let from_backlog = self.backlog.peek().is_some_and(|message| {
+            self.bandwidth_queue
+                .peek_closest()
+                .is_none_or(|closest| message.0.order() < closest)
+        });
+
+        let next_event = if from_backlog {
+            self.backlog.pop().map(|message| message.0)
+        } else {
+            self.bandwidth_queue.pop()
+        };
 
         match next_event {
-            None => {}
-            Some(message) => {
-                self.execute_process_step(message.step);
-            }
+            // Moved from the latency queue into the NIC buffers
+            None => false,
+            Some(mut message) => match cpu::busy_until(message.step.dest) {
+                Some(until) => {
+                    debug!(
+                        "P{} is busy, deferring message until {until}",
+                        message.step.dest
+                    );
+                    message.arrival_time = until;
+                    self.backlog.push(Reverse(message));
+                    false
+                }
+                None => {
+                    self.execute_process_step(message.step);
+                    true
+                }
+            },
         }
     }
 
-    fn peek_closest(&self) -> Option<Jiffies> {
-        self.bandwidth_queue.peek_closest()
+    fn peek_closest(&self) -> Option<(Jiffies, TieKey)> {
+        let deferred = self.backlog.peek().map(|message| message.0.order());
+        match (self.bandwidth_queue.peek_closest(), deferred) {
+            (Some(closest), Some(deferred)) => Some(closest.min(deferred)),
+            (closest, deferred) => closest.or(deferred),
+        }
     }
 }
 
 impl EventSubmitter for Network {
-    type Event = (ProcessId, Destination, Rc<dyn Message>);
+    type Event = (ProcessId, Destination, Rc<dyn Message>, Jiffies);
 
     fn submit(&mut self, events: &mut Vec<Self::Event>) {
-        events.drain(..).for_each(|(from, destination, message)| {
-            self.submit_single_message(message, from, destination);
-        });
+        events
+            .drain(..)
+            .for_each(|(from, destination, message, departure)| {
+                self.submit_single_message(message, from, destination, departure);
+            });
     }
 }
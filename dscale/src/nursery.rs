@@ -1,4 +1,6 @@
 use std::{
+    any::Any,
+    cell::RefCell,
     collections::{BTreeMap, btree_map::Keys},
     rc::Rc,
 };
@@ -6,38 +8,118 @@ use std::{
 use log::debug;
 
 use crate::{
-    ProcessId, communication::DScaleMessage, global::set_process, process::MutableProcessHandle,
+    ProcessHandle, ProcessId,
+    communication::DScaleMessage,
+    global::{cpu, set_process},
+    process::{MutableProcessHandle, UniqueProcessHandle},
+    storage,
+    trace::{self, Handler},
 };
 
 pub(crate) type HandlerMap = BTreeMap<ProcessId, MutableProcessHandle>; // btree for deterministic iterators
 
+// Copies a process of a cloneable pool, see SimulationBuilder::add_cloneable_pool
+pub(crate) type Forker = fn(&dyn ProcessHandle) -> UniqueProcessHandle;
+
+pub(crate) fn fork_process<P: ProcessHandle + Clone>(
+    process: &dyn ProcessHandle,
+) -> UniqueProcessHandle {
+    Box::new(
+        (process as &dyn Any)
+            .downcast_ref::<P>()
+            .expect("Process type should match its pool")
+            .clone(),
+    )
+}
+
 pub(crate) struct Nursery {
     procs: HandlerMap,
+    forkers: BTreeMap<ProcessId, Forker>,
 }
 
 impl Nursery {
-    pub(crate) fn new(procs: HandlerMap) -> Rc<Self> {
-        Rc::new(Self { procs })
+    pub(crate) fn new(procs: HandlerMap, forkers: BTreeMap<ProcessId, Forker>) -> Rc<Self> {
+        Rc::new(Self { procs, forkers })
+    }
+
+    // Deep copy of all processes, each of them should be cloneable
+    pub(crate) fn fork(&self) -> Rc<Self> {
+        let procs = self
+            .procs
+            .iter()
+            .map(|(id, handle)| {
+                let fork = self.forkers.get(id).unwrap_or_else(|| {
+                    panic!("P{id} is not cloneable, add its pool with add_cloneable_pool")
+                });
+                (*id, RefCell::new(fork(&**handle.borrow())))
+            })
+            .collect();
+        Nursery::new(procs, self.forkers.clone())
+    }
+
+    // Runs host code against a process as if it was its handler at the current time
+    pub(crate) fn with_process<P: ProcessHandle, T>(
+        &self,
+        id: ProcessId,
+        f: impl FnOnce(&mut P) -> T,
+    ) -> T {
+        let mut handle = self.procs.get(&id).expect("Invalid ProcessId").borrow_mut();
+        set_process(id);
+        cpu::begin_handler();
+        let result = f((&mut **handle as &mut dyn Any)
+            .downcast_mut::<P>()
+            .expect("Wrong process type"));
+        cpu::end_handler(id);
+        result
     }
 
     pub(crate) fn start_single(&self, id: ProcessId) {
         set_process(id);
         debug!("Starting P{id}");
+        cpu::begin_handler();
         self.procs
             .get(&id)
             .expect("Invalid ProcessId")
             .borrow_mut()
             .start();
+        trace::record(id, Handler::Start);
+        cpu::end_handler(id);
     }
 
     pub(crate) fn deliver(&self, from: ProcessId, to: ProcessId, m: DScaleMessage) {
         let mut handle = self.procs.get(&to).expect("Invalid ProcessId").borrow_mut();
         set_process(to);
         debug!("Executing step for From: P{} | To: P{}", to, from);
-        match m {
-            DScaleMessage::NetworkMessage(ptr) => handle.on_message(from, ptr),
-            DScaleMessage::Timer(id) => handle.on_timer(id),
-        }
+        cpu::begin_handler();
+        let handler = match m {
+            DScaleMessage::NetworkMessage(ptr, sent_at) => {
+                let handler = Handler::Message {
+                    from,
+                    name: ptr.0.name(),
+                    size: ptr.0.virtual_size(),
+                    sent_at,
+                };
+                cpu::consume_cpu(ptr.0.cpu_cost());
+                handle.on_message(from, ptr);
+                handler
+            }
+            DScaleMessage::Timer(id) => match storage::take_completion(id) {
+                None => {
+                    handle.on_timer(id);
+                    Handler::Timer(id)
+                }
+                Some(Some(completion)) => {
+                    handle.on_storage(completion);
+                    Handler::Storage(id)
+                }
+                Some(None) => {
+                    debug!("Storage operation {id} of P{to} was lost in a crash");
+                    return;
+                }
+            },
+        };
+        trace::record(to, handler);
+        cpu::end_handler(to);
     }
 
     pub(crate) fn keys(&self) -> Keys<'_, ProcessId, MutableProcessHandle> {
@@ -4,9 +4,9 @@
 //! by all processes in DScale simulations, as well as the `ProcessId` type used
 //! for process identification throughout the system.
 
-use std::cell::RefCell;
+use std::{any::Any, cell::RefCell};
 
-use crate::{MessagePtr, time::timer_manager::TimerId};
+use crate::{MessagePtr, storage::Completion, time::timer_manager::TimerId};
 
 /// Unique identifier for a process within a simulation.
 ///
@@ -170,7 +170,7 @@ pub(crate) type MutableProcessHandle = RefCell<UniqueProcessHandle>;
 ///     #[default]
 ///     Initializing,
 ///     Active,
-///     Shutting Down,
+///     ShuttingDown,
 /// }
 ///
 /// impl ProcessHandle for StatefulProcess {
@@ -241,7 +241,7 @@ pub(crate) type MutableProcessHandle = RefCell<UniqueProcessHandle>;
 /// [`list_pool`]: crate::list_pool
 /// [`choose_from_pool`]: crate::choose_from_pool
 /// [`global_unique_id`]: crate::global_unique_id
-pub trait ProcessHandle {
+pub trait ProcessHandle: Any {
     /// Initialize the process and schedule initial work.
     ///
     /// This method is called exactly once for each process at the beginning
@@ -612,4 +612,50 @@ pub trait ProcessHandle {
     /// [`schedule_timer_after`]: crate::schedule_timer_after
     /// [`TimerId`]: crate::TimerId
     fn on_timer(&mut self, id: TimerId);
+
+    /// Called when a storage operation of this process completes.
+    ///
+    /// Operations started with [`storage::write`], [`storage::read`] and
+    /// [`storage::fsync`] complete asynchronously, like timers. The
+    /// [`Completion`] carries the [`OperationId`] returned when the operation
+    /// was started. Operations lost in a [`storage::crash`] never complete.
+    ///
+    /// The default implementation ignores completions, so processes that do
+    /// not use storage need not implement it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use dscale::{ProcessHandle, ProcessId, MessagePtr, TimerId};
+    /// use dscale::storage::{self, Completion};
+    ///
+    /// #[derive(Default)]
+    /// struct Recovering {
+    ///     term: u64,
+    /// }
+    ///
+    /// impl ProcessHandle for Recovering {
+    ///     fn start(&mut self) {
+    ///         storage::read("term", 8);
+    ///     }
+    ///
+    ///     fn on_message(&mut self, from: ProcessId, message: MessagePtr) {}
+    ///     fn on_timer(&mut self, id: TimerId) {}
+    ///
+    ///     fn on_storage(&mut self, completion: Completion) {
+    ///         if let Completion::Read(_, Some(value)) = completion {
+    ///             self.term = *value.downcast_ref::<u64>().expect("Term is u64");
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// [`storage::write`]: crate::storage::write
+    /// [`storage::read`]: crate::storage::read
+    /// [`storage::fsync`]: crate::storage::fsync
+    /// [`storage::crash`]: crate::storage::crash
+    /// [`OperationId`]: crate::storage::OperationId
+    fn on_storage(&mut self, completion: Completion) {
+        let _ = completion;
+    }
 }
@@ -27,7 +27,7 @@ impl Bar {
         };
 
         Self {
-            bar: bar,
+            bar,
             prev_log: 0,
             delta: total.0 / K_PROGRESS_TIMES,
         }
@@ -20,6 +20,10 @@ pub type Seed = u64;
 /// is commonly used for latency modeling, though they can be applied to any
 /// stochastic simulation parameter.
 ///
+/// Latencies handed to [`SimulationBuilder::latency_topology`] may also be
+/// given in real time, as `Distributions<Duration>`; they are converted to
+/// jiffies when the simulation is built.
+///
 /// # Deterministic Randomness
 ///
 /// All distributions use the simulation's deterministic random number generator,
@@ -44,6 +48,8 @@ pub type Seed = u64;
 ///
 /// let simulation = SimulationBuilder::default()
 ///     .add_pool::<MyProcess>("servers", 3)
+///     .add_pool::<MyProcess>("clients", 2)
+///     .add_pool::<MyProcess>("mobile", 4)
 ///     .latency_topology(&[
 ///         // Low-latency local network with small variation
 ///         LatencyDescription::WithinPool("servers",
@@ -67,15 +73,51 @@ pub type Seed = u64;
 /// # }
 /// ```
 ///
+/// Latencies in real time:
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use dscale::{SimulationBuilder, LatencyDescription, Distributions};
+///
+/// let simulation = SimulationBuilder::default()
+///     .add_pool::<MyProcess>("servers", 3)
+///     .latency_topology(&[LatencyDescription::WithinPool(
+///         "servers",
+///         Distributions::Normal(Duration::from_millis(20), Duration::from_millis(5)),
+///     )])
+///     .jiffy(Duration::from_micros(100)) // Mean of 200 jiffies
+///     .build();
+/// # struct MyProcess;
+/// # impl Default for MyProcess { fn default() -> Self { MyProcess } }
+/// # impl dscale::ProcessHandle for MyProcess {
+/// #     fn start(&mut self) {}
+/// #     fn on_message(&mut self, from: dscale::ProcessId, message: dscale::MessagePtr) {}
+/// #     fn on_timer(&mut self, id: dscale::TimerId) {}
+/// # }
+/// ```
+///
 /// [`Jiffies`]: crate::Jiffies
 /// [`LatencyDescription`]: crate::LatencyDescription
+/// [`SimulationBuilder::latency_topology`]: crate::SimulationBuilder::latency_topology
 #[derive(Copy, Clone)]
-pub enum Distributions {
-    Uniform(Jiffies, Jiffies),
-    Bernoulli(f64, Jiffies),
-    Normal(Jiffies, Jiffies),
+pub enum Distributions<T = Jiffies> {
+    Uniform(T, T),
+    Bernoulli(f64, T),
+    Normal(T, T),
 }
 
+impl<T> Distributions<T> {
+    pub(crate) fn map<U>(self, f: impl Fn(T) -> U) -> Distributions<U> {
+        match self {
+            Distributions::Uniform(from, to) => Distributions::Uniform(f(from), f(to)),
+            Distributions::Bernoulli(p, value) => Distributions::Bernoulli(p, f(value)),
+            Distributions::Normal(mean, std_dev) => Distributions::Normal(f(mean), f(std_dev)),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Randomizer {
     rnd: rand::rngs::StdRng,
 }
@@ -104,7 +146,11 @@ impl Randomizer {
         }
     }
 
-    pub fn choose_from_slice<'a, T: Copy>(&mut self, from: &[T]) -> T {
+    pub fn random_u64(&mut self) -> u64 {
+        self.rnd.random()
+    }
+
+    pub fn choose_from_slice<T: Copy>(&mut self, from: &[T]) -> T {
         from.choose(&mut self.rnd)
             .copied()
             .expect("Chose from empty slice")
@@ -0,0 +1,590 @@
+//! Declarative scenario files.
+//!
+//! A [`Scenario`] describes an experiment in TOML instead of Rust: pools and
+//! the process type each of them runs, the latency and bandwidth topology,
+//! the seed, the time budget, faults injected during the run and parameters
+//! handed to the processes. Process types are looked up by name in a
+//! [`Registry`] filled by the program running the scenario, so a scenario can
+//! be changed and rerun without recompiling anything.
+//!
+//! Requires the `scenario` feature.
+//!
+//! # Faults
+//!
+//! A fault takes effect at its `at` time: the run stops before the first
+//! event due then, the clock moves to `at` and the fault is injected. Only
+//! storage crashes and clock skew can be expressed; the format has no way to
+//! crash a process or to drop, delay or duplicate messages, which needs a
+//! [`Simulation`](crate::Simulation) driven from Rust.
+//!
+//! # Format
+//!
+//! ```toml
+//! seed = 42
+//! jiffy = "1ms"             # Real-time length of a jiffy, 1ms by default
+//! time_budget = "60s"       # Times are jiffies (integers) or "<number><unit>"
+//!                           # with unit ns, us, ms, s, m or h
+//! bandwidth = { mbps = 10_000 }   # Or "unbounded", { gbps = 10 }, { bytes_per_jiffy = 1000 }
+//!
+//! # Process ids are assigned in the order pools are listed, starting from 1
+//! [[pools]]
+//! name = "Validators"
+//! process = "bullshark"     # Name in the registry
+//! size = 100
+//! cores = 4                 # Optional
+//!
+//! [[latency]]
+//! pool = "Validators"
+//! distribution = { normal = ["50ms", "10ms"] }  # Or uniform = [min, max], bernoulli = [p, time]
+//!
+//! [[latency]]
+//! from = "Validators"
+//! to = "Clients"
+//! distribution = { uniform = [10, 20] }
+//!
+//! [[faults]]
+//! at = "30s"
+//! storage_crash = 3
+//!
+//! [[faults]]
+//! at = "40s"
+//! clock_skew = { process = 1, offset = -200, drift = 0.01 }
+//!
+//! # Stored in anykv before the processes are created: integers as usize,
+//! # floats as f64, strings as String and booleans as bool
+//! [parameters]
+//! D = 15
+//! ```
+//!
+//! # Examples
+//!
+//! ```rust
+//! use dscale::scenario::{Registry, Scenario};
+//! use dscale::*;
+//!
+//! struct Ping;
+//! impl Message for Ping {}
+//!
+//! #[derive(Default)]
+//! struct Node;
+//!
+//! impl ProcessHandle for Node {
+//!     fn start(&mut self) {
+//!         let rounds = global::anykv::get::<usize>("rounds");
+//!         (0..rounds).for_each(|_| broadcast(Ping));
+//!         schedule_timer_after(Jiffies(100));
+//!     }
+//!     fn on_message(&mut self, _from: ProcessId, _message: MessagePtr) {}
+//!     fn on_timer(&mut self, _id: TimerId) {
+//!         schedule_timer_after(Jiffies(100));
+//!     }
+//! }
+//!
+//! let mut registry = Registry::new();
+//! registry.register::<Node>("node");
+//!
+//! let scenario = Scenario::from_toml(
+//!     r#"
+//!     seed = 7
+//!     time_budget = "1s"
+//!     bandwidth = { mbps = 100 }
+//!
+//!     [[pools]]
+//!     name = "nodes"
+//!     process = "node"
+//!     size = 4
+//!
+//!     [[latency]]
+//!     pool = "nodes"
+//!     distribution = { uniform = ["1ms", "5ms"] }
+//!
+//!     [parameters]
+//!     rounds = 3
+//!     "#,
+//! )
+//! .unwrap();
+//!
+//! let report = scenario.run(&registry).unwrap();
+//! assert_eq!(report.statistics.total_traffic().messages, 4 * 3 * 4);
+//! ```
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    error::Error,
+    fmt, fs,
+    path::Path,
+    time::Duration,
+};
+
+use serde::Deserialize;
+
+use crate::{
+    BandwidthDescription, ClockSkew, Distributions, ProcessHandle, ProcessId, SimulationBuilder,
+    global::anykv,
+    random::Seed,
+    stats::Statistics,
+    storage,
+    time::{Jiffies, Time},
+};
+
+/// Why a scenario could not be loaded or run.
+#[derive(Debug)]
+pub enum ScenarioError {
+    /// The scenario file could not be read
+    Io(std::io::Error),
+    /// The scenario is not valid TOML or does not follow the format
+    Parse(String),
+    /// The scenario refers to something that does not exist, such as an
+    /// unregistered process type or an unknown pool
+    Invalid(String),
+}
+
+impl fmt::Display for ScenarioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScenarioError::Io(error) => write!(f, "Cannot read scenario: {error}"),
+            ScenarioError::Parse(error) => write!(f, "Malformed scenario: {error}"),
+            ScenarioError::Invalid(error) => write!(f, "Invalid scenario: {error}"),
+        }
+    }
+}
+
+impl Error for ScenarioError {}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawTime {
+    Jiffies(usize),
+    Text(String),
+}
+
+// Jiffies as integers, real time as "<number><unit>"
+impl<'de> Deserialize<'de> for Time {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        match RawTime::deserialize(deserializer)? {
+            RawTime::Jiffies(jiffies) => Ok(Time::Jiffies(Jiffies(jiffies))),
+            RawTime::Text(text) => parse_duration(&text)
+                .map(Time::Real)
+                .map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+// "<number><unit>", e.g. "1.5s" or "200us"
+fn parse_duration(text: &str) -> Result<Duration, String> {
+    let split = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .ok_or_else(|| format!("Missing time unit in {text:?}"))?;
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("Invalid number in {text:?}"))?;
+    let seconds = match unit.trim() {
+        "ns" => 1e-9,
+        "us" => 1e-6,
+        "ms" => 1e-3,
+        "s" => 1.0,
+        "m" => 60.0,
+        "h" => 3600.0,
+        other => return Err(format!("Unknown time unit {other:?} in {text:?}")),
+    };
+    Ok(Duration::from_secs_f64(number * seconds))
+}
+
+fn deserialize_real_time<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let text = String::deserialize(deserializer)?;
+    let duration = parse_duration(&text).map_err(serde::de::Error::custom)?;
+    if duration.is_zero() {
+        return Err(serde::de::Error::custom("A jiffy should last some time"));
+    }
+    Ok(Some(duration))
+}
+
+/// A pool of processes of a registered type.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PoolSpec {
+    pub name: String,
+    /// Name the process type is registered with in the [`Registry`]
+    pub process: String,
+    pub size: usize,
+    /// Virtual cores of every process, see [`SimulationBuilder::cores`]
+    pub cores: Option<usize>,
+}
+
+/// Latency distribution, see [`Distributions`].
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DistributionSpec {
+    Uniform(Time, Time),
+    Bernoulli(f64, Time),
+    Normal(Time, Time),
+}
+
+impl DistributionSpec {
+    fn distribution(&self) -> Distributions<Time> {
+        match *self {
+            DistributionSpec::Uniform(min, max) => Distributions::Uniform(min, max),
+            DistributionSpec::Bernoulli(p, time) => Distributions::Bernoulli(p, time),
+            DistributionSpec::Normal(mean, deviation) => Distributions::Normal(mean, deviation),
+        }
+    }
+}
+
+/// Latency within a pool or between two pools, see
+/// [`LatencyDescription`](crate::LatencyDescription).
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged, deny_unknown_fields)]
+pub enum LatencySpec {
+    Within {
+        pool: String,
+        distribution: DistributionSpec,
+    },
+    Between {
+        from: String,
+        to: String,
+        distribution: DistributionSpec,
+    },
+}
+
+/// NIC bandwidth of every process, see [`BandwidthDescription`].
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BandwidthSpec {
+    #[default]
+    Unbounded,
+    BytesPerJiffy(usize),
+    Mbps(usize),
+    Gbps(usize),
+}
+
+impl From<BandwidthSpec> for BandwidthDescription {
+    fn from(spec: BandwidthSpec) -> Self {
+        match spec {
+            BandwidthSpec::Unbounded => BandwidthDescription::Unbounded,
+            BandwidthSpec::BytesPerJiffy(bytes) => BandwidthDescription::Bounded(bytes),
+            BandwidthSpec::Mbps(mbps) => BandwidthDescription::Mbps(mbps),
+            BandwidthSpec::Gbps(gbps) => BandwidthDescription::Gbps(gbps),
+        }
+    }
+}
+
+/// A fault injected once the simulation reaches a point in time.
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct FaultSpec {
+    pub at: Time,
+    #[serde(flatten)]
+    pub fault: Fault,
+}
+
+/// Faults a scenario can inject from outside of the processes.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Fault {
+    /// Crashes the disk of a process, see [`storage::crash`]
+    StorageCrash(ProcessId),
+    /// Skews the clock of a process, see [`set_clock_skew`](crate::set_clock_skew)
+    ClockSkew {
+        process: ProcessId,
+        offset: i64,
+        drift: f64,
+    },
+}
+
+impl Fault {
+    fn inject(&self) {
+        match *self {
+            Fault::StorageCrash(process) => storage::crash(process),
+            Fault::ClockSkew {
+                process,
+                offset,
+                drift,
+            } => crate::set_clock_skew(process, ClockSkew { offset, drift }),
+        }
+    }
+
+    fn process(&self) -> ProcessId {
+        match *self {
+            Fault::StorageCrash(process) | Fault::ClockSkew { process, .. } => process,
+        }
+    }
+}
+
+/// A parsed scenario, see the [module documentation](self) for the format.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    #[serde(default = "default_seed")]
+    pub seed: Seed,
+    /// Real-time length of a jiffy, see [`SimulationBuilder::jiffy`]
+    #[serde(default, deserialize_with = "deserialize_real_time")]
+    pub jiffy: Option<Duration>,
+    pub time_budget: Time,
+    pub pools: Vec<PoolSpec>,
+    #[serde(default)]
+    pub latency: Vec<LatencySpec>,
+    #[serde(default)]
+    pub bandwidth: BandwidthSpec,
+    #[serde(default)]
+    pub faults: Vec<FaultSpec>,
+    #[serde(default)]
+    pub parameters: BTreeMap<String, toml::Value>,
+}
+
+// Same as SimulationBuilder
+fn default_seed() -> Seed {
+    69
+}
+
+/// Outcome of [`Scenario::run`].
+///
+/// `Display` renders the statistics followed by the reports of the process
+/// types.
+#[derive(Clone, Debug)]
+pub struct ScenarioReport {
+    pub statistics: Statistics,
+    /// Report of every process type with a [`Registration::report`] hook,
+    /// by registered name
+    pub reports: BTreeMap<String, String>,
+}
+
+impl fmt::Display for ScenarioReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.statistics)?;
+        for (name, report) in &self.reports {
+            writeln!(f, "{name}:")?;
+            for line in report.lines() {
+                writeln!(f, "  {line}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Scenario {
+    /// Parses a scenario.
+    pub fn from_toml(text: &str) -> Result<Self, ScenarioError> {
+        toml::from_str(text).map_err(|error| ScenarioError::Parse(error.to_string()))
+    }
+
+    /// Reads and parses a scenario file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ScenarioError> {
+        let text = fs::read_to_string(path).map_err(ScenarioError::Io)?;
+        Self::from_toml(&text)
+    }
+
+    /// Builds and runs the scenario until its time budget is exhausted,
+    /// injecting its faults on the way.
+    ///
+    /// Parameters are stored in anykv and the
+    /// [`setup`](Registration::setup) hooks of the used process types run
+    /// before the processes are created; the [`report`](Registration::report)
+    /// hooks run at the end, while anykv still holds the results.
+    ///
+    /// Like [`Simulation::run`](crate::Simulation::run), exits the program
+    /// if the simulation deadlocks.
+    pub fn run(&self, registry: &Registry) -> Result<ScenarioReport, ScenarioError> {
+        let used = self.validate(registry)?;
+
+        self.parameters.iter().try_for_each(|(key, value)| {
+            set_parameter(key, value)
+                .ok_or_else(|| invalid(format!("Unsupported type of parameter {key:?}")))
+        })?;
+        used.iter().for_each(|name| {
+            if let Some(setup) = &registry.types[name].setup {
+                setup();
+            }
+        });
+
+        let mut builder = SimulationBuilder::default();
+        if let Some(jiffy) = self.jiffy {
+            builder = builder.jiffy(jiffy);
+        }
+        builder = builder.seed(self.seed).time_budget(self.time_budget);
+        for pool in &self.pools {
+            builder = (registry.types[&pool.process].add)(builder, &pool.name, pool.size);
+            if let Some(cores) = pool.cores {
+                builder = builder.cores(&pool.name, cores);
+            }
+        }
+        for latency in &self.latency {
+            match latency {
+                LatencySpec::Within { pool, distribution } => {
+                    builder.set_pool_latency(pool, pool, distribution.distribution());
+                }
+                LatencySpec::Between {
+                    from,
+                    to,
+                    distribution,
+                } => {
+                    builder.set_pool_latency(from, to, distribution.distribution());
+                }
+            }
+        }
+        let mut simulation = builder.nic_bandwidth(self.bandwidth.into()).build();
+
+        let jiffy = crate::global::jiffy();
+        let mut faults = self.faults.clone();
+        faults.sort_by_key(|fault| fault.at.in_jiffies(jiffy));
+        for fault in faults {
+            simulation.run_until(fault.at.in_jiffies(jiffy));
+            fault.fault.inject();
+        }
+        simulation.run();
+
+        let reports = used
+            .iter()
+            .filter_map(|name| {
+                let report = registry.types[name].report.as_ref()?;
+                Some((name.clone(), report()))
+            })
+            .collect();
+        Ok(ScenarioReport {
+            statistics: simulation.statistics(),
+            reports,
+        })
+    }
+
+    // Registered names of the used process types
+    fn validate(&self, registry: &Registry) -> Result<BTreeSet<String>, ScenarioError> {
+        let mut pools = BTreeSet::new();
+        for pool in &self.pools {
+            if !registry.types.contains_key(&pool.process) {
+                return Err(invalid(format!(
+                    "Process type {:?} of pool {:?} is not registered, known: {}",
+                    pool.process,
+                    pool.name,
+                    registry.names().collect::<Vec<_>>().join(", ")
+                )));
+            }
+            if !pools.insert(pool.name.as_str()) {
+                return Err(invalid(format!("Pool {:?} is listed twice", pool.name)));
+            }
+        }
+
+        let known_pool = |name: &String| {
+            pools
+                .contains(name.as_str())
+                .then_some(())
+                .ok_or_else(|| invalid(format!("Latency refers to unknown pool {name:?}")))
+        };
+        for latency in &self.latency {
+            match latency {
+                LatencySpec::Within { pool, .. } => known_pool(pool)?,
+                LatencySpec::Between { from, to, .. } => {
+                    known_pool(from)?;
+                    known_pool(to)?;
+                }
+            }
+        }
+
+        let processes: usize = self.pools.iter().map(|pool| pool.size).sum();
+        for fault in &self.faults {
+            let process = fault.fault.process();
+            if process == 0 || process > processes {
+                return Err(invalid(format!("Fault targets unknown process {process}")));
+            }
+        }
+
+        Ok(self.pools.iter().map(|pool| pool.process.clone()).collect())
+    }
+}
+
+fn invalid(message: String) -> ScenarioError {
+    ScenarioError::Invalid(message)
+}
+
+// None for arrays and tables
+fn set_parameter(key: &str, value: &toml::Value) -> Option<()> {
+    match value {
+        toml::Value::Integer(integer) => anykv::set::<usize>(key, usize::try_from(*integer).ok()?),
+        toml::Value::Float(float) => anykv::set::<f64>(key, *float),
+        toml::Value::String(string) => anykv::set::<String>(key, string.clone()),
+        toml::Value::Boolean(boolean) => anykv::set::<bool>(key, *boolean),
+        _ => return None,
+    }
+    Some(())
+}
+
+/// How a registered process type is added to a simulation, with optional
+/// hooks, see [`Registry::register`].
+pub struct Registration {
+    add: fn(SimulationBuilder, &str, usize) -> SimulationBuilder,
+    setup: Option<Box<dyn Fn()>>,
+    report: Option<Box<dyn Fn() -> String>>,
+}
+
+impl Registration {
+    /// Runs `setup` before the processes of a scenario using the type are
+    /// created, e.g. to initialize the anykv entries they update.
+    pub fn setup(&mut self, setup: impl Fn() + 'static) -> &mut Self {
+        self.setup = Some(Box::new(setup));
+        self
+    }
+
+    /// Runs `report` after a scenario using the type finished, e.g. to
+    /// summarize the results the processes left in anykv.
+    pub fn report(&mut self, report: impl Fn() -> String + 'static) -> &mut Self {
+        self.report = Some(Box::new(report));
+        self
+    }
+}
+
+/// Process types a scenario can refer to by name.
+#[derive(Default)]
+pub struct Registry {
+    types: BTreeMap<String, Registration>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a process type under a name, replacing an earlier
+    /// registration of the name.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use dscale::global::anykv;
+    /// use dscale::scenario::Registry;
+    /// # use dscale::{MessagePtr, ProcessHandle, ProcessId, TimerId};
+    /// # #[derive(Default)]
+    /// # struct Replica;
+    /// # impl ProcessHandle for Replica {
+    /// #     fn start(&mut self) {}
+    /// #     fn on_message(&mut self, _from: ProcessId, _message: MessagePtr) {}
+    /// #     fn on_timer(&mut self, _id: TimerId) {}
+    /// # }
+    ///
+    /// let mut registry = Registry::new();
+    /// registry
+    ///     .register::<Replica>("replica")
+    ///     .setup(|| anykv::set::<usize>("commits", 0))
+    ///     .report(|| format!("commits: {}", anykv::get::<usize>("commits")));
+    /// ```
+    pub fn register<P: ProcessHandle + Default + 'static>(
+        &mut self,
+        name: &str,
+    ) -> &mut Registration {
+        let registration = Registration {
+            add: |builder, pool, size| builder.add_pool::<P>(pool, size),
+            setup: None,
+            report: None,
+        };
+        self.types.insert(name.to_string(), registration);
+        self.types.get_mut(name).expect("Just registered")
+    }
+
+    /// Registered names in alphabetical order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.types.keys().map(String::as_str)
+    }
+}
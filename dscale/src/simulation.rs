@@ -5,19 +5,34 @@
 //! struct orchestrates all simulation actors including network, timers, and
 //! process execution in a deterministic, single-threaded environment.
 
-use std::{cell::RefCell, process::exit, rc::Rc, usize};
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    process::exit,
+    rc::Rc,
+    time::{Duration, Instant},
+};
 
 use log::{error, info};
 
 use crate::{
+    ClockSkew, ProcessHandle, ProcessId,
     actor::SharedActor,
-    global,
-    network::{BandwidthDescription, Network},
-    nursery::{HandlerMap, Nursery},
+    explore::Transition,
+    global::{self, TieBreaking, TieKey},
+    network::{BandwidthDescription, Network, NetworkActor},
+    nursery::{Forker, HandlerMap, Nursery},
     progress::Bar,
     random::{self, Randomizer},
-    time::{Jiffies, timer_manager::TimerManager},
+    snapshot::Snapshot,
+    stats::Statistics,
+    storage::{self, StorageDescription},
+    time::{
+        Jiffies,
+        timer_manager::{TimerManager, TimerManagerActor},
+    },
     topology::{LatencyTopology, PoolListing, Topology},
+    trace::{self, Trace},
 };
 
 /// The main simulation engine that executes distributed system simulations.
@@ -51,7 +66,7 @@ use crate::{
 ///
 /// Typically, you don't create `Simulation` directly but use [`SimulationBuilder`]:
 ///
-/// ```rust
+/// ```rust,no_run
 /// use dscale::{SimulationBuilder, ProcessHandle, ProcessId, MessagePtr, TimerId, Jiffies};
 ///
 /// #[derive(Default)]
@@ -81,22 +96,43 @@ use crate::{
 ///
 /// [`SimulationBuilder`]: crate::SimulationBuilder
 pub struct Simulation {
+    id: usize,
     actors: Vec<SharedActor>,
+    network: NetworkActor,
+    timers: TimerManagerActor,
+    nursery: Rc<Nursery>,
+    topology: Rc<Topology>,
+    seed: random::Seed,
     time_budget: Jiffies,
     progress_bar: Bar,
+    events: usize,
+    // Executed events of each actor, in the order of actors
+    actor_events: Vec<usize>,
+    wall_clock: Duration,
+    started: bool,
 }
 
 impl Simulation {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         seed: random::Seed,
         time_budget: Jiffies,
+        jiffy: Duration,
+        tie_breaking: TieBreaking,
+        cores: BTreeMap<ProcessId, usize>,
+        clock_skews: BTreeMap<ProcessId, ClockSkew>,
+        storage: BTreeMap<ProcessId, StorageDescription>,
+        record_trace: bool,
         bandwidth: BandwidthDescription,
         latency_topology: LatencyTopology,
         pool_listing: PoolListing,
         procs: HandlerMap,
+        forkers: BTreeMap<ProcessId, Forker>,
     ) -> Self {
+        // Bandwidths in bits per second are converted with it
+        global::setup_jiffy(jiffy);
         let topology = Topology::new_shared(pool_listing.clone(), latency_topology);
-        let nursery = Nursery::new(procs);
+        let nursery = Nursery::new(procs, forkers);
 
         let network_actor = Rc::new(RefCell::new(Network::new(
             seed,
@@ -108,19 +144,72 @@ impl Simulation {
         let timers_actor = Rc::new(RefCell::new(TimerManager::new(nursery.clone())));
 
         global::configuration::setup_global_configuration(nursery.size());
+        global::setup_order(tie_breaking, seed);
+        global::setup_cpu(&cores);
+        global::setup_skew(&clock_skews);
+        storage::setup_storage(&storage, seed);
+        trace::setup_trace(record_trace, &pool_listing, jiffy);
         global::setup_access(
             network_actor.clone(),
             timers_actor.clone(),
-            topology,
+            topology.clone(),
             Randomizer::new(seed),
         );
 
-        let actors: Vec<SharedActor> = vec![network_actor, timers_actor];
+        let actors: Vec<SharedActor> = vec![network_actor.clone(), timers_actor.clone()];
 
         Self {
+            id: global::activate_simulation(),
             actors,
+            network: network_actor,
+            timers: timers_actor,
+            nursery,
+            topology,
+            seed,
             time_budget,
             progress_bar: Bar::new(time_budget),
+            events: 0,
+            actor_events: vec![0; 2],
+            wall_clock: Duration::ZERO,
+            started: false,
+        }
+    }
+
+    // Continuation of a snapshot with its own copy of processes and pending events
+    pub(crate) fn from_snapshot(snapshot: &Snapshot) -> Self {
+        let nursery = snapshot.nursery.fork();
+        let network_actor = Rc::new(RefCell::new(snapshot.network.fork(nursery.clone())));
+        let timers_actor = Rc::new(RefCell::new(snapshot.timers.fork(nursery.clone())));
+
+        // Everything but anykv is overwritten, host values there stay visible to the fork
+        global::configuration::setup_global_configuration(nursery.size());
+        nursery.keys().for_each(|id| {
+            global::configuration::setup_local_configuration(*id, snapshot.seed);
+        });
+        global::restore_all(&snapshot.globals);
+        global::setup_access(
+            network_actor.clone(),
+            timers_actor.clone(),
+            snapshot.topology.clone(),
+            snapshot.access_random.clone(),
+        );
+
+        let actors: Vec<SharedActor> = vec![network_actor.clone(), timers_actor.clone()];
+
+        Self {
+            id: global::activate_simulation(),
+            actors,
+            network: network_actor,
+            timers: timers_actor,
+            nursery,
+            topology: snapshot.topology.clone(),
+            seed: snapshot.seed,
+            time_budget: snapshot.time_budget,
+            progress_bar: Bar::new(snapshot.time_budget),
+            events: snapshot.events,
+            actor_events: snapshot.actor_events.clone(),
+            wall_clock: snapshot.wall_clock,
+            started: true,
         }
     }
 
@@ -157,7 +246,7 @@ impl Simulation {
     ///
     /// # Examples
     ///
-    /// ```rust
+    /// ```rust,no_run
     /// use dscale::{SimulationBuilder, Jiffies};
     ///
     /// let mut simulation = SimulationBuilder::default()
@@ -182,61 +271,446 @@ impl Simulation {
     /// is detected. Use `RUST_LOG=debug` for detailed information about the
     /// deadlock condition.
     pub fn run(&mut self) {
-        self.start();
+        let outcome = self.run_to_outcome();
+        info!("Statistics:\n{}", self.statistics());
+        match outcome {
+            RunOutcome::Deadlock => {
+                error!("DEADLOCK! (ﾉಥ益ಥ）ﾉ ┻━┻ Try with RUST_LOG=debug");
+                exit(1)
+            }
+            RunOutcome::Finished => info!("Looks good! ヽ('ー`)ノ"),
+        }
+    }
 
-        while global::now() < self.time_budget {
+    /// Takes the handler invocations recorded so far.
+    ///
+    /// Returns an empty [`Trace`] unless recording was enabled with
+    /// [`SimulationBuilder::record_trace`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dscale::SimulationBuilder;
+    /// # use dscale::{ProcessHandle, ProcessId, MessagePtr, TimerId};
+    /// # #[derive(Default)]
+    /// # struct MyProcess;
+    /// # impl ProcessHandle for MyProcess {
+    /// #     fn start(&mut self) {}
+    /// #     fn on_message(&mut self, from: ProcessId, message: MessagePtr) {}
+    /// #     fn on_timer(&mut self, id: TimerId) {}
+    /// # }
+    ///
+    /// let mut simulation = SimulationBuilder::default()
+    ///     .add_pool::<MyProcess>("nodes", 3)
+    ///     .record_trace()
+    ///     .build();
+    /// simulation.run();
+    ///
+    /// let trace = simulation.take_trace();
+    /// println!("{} handler invocations", trace.invocations.len());
+    /// ```
+    ///
+    /// [`Trace`]: crate::trace::Trace
+    /// [`SimulationBuilder::record_trace`]: crate::SimulationBuilder::record_trace
+    pub fn take_trace(&mut self) -> Trace {
+        trace::take_trace()
+    }
+
+    /// Number of events executed so far: process handler invocations for
+    /// delivered messages, fired timers and completed storage operations.
+    ///
+    /// Internal steps of the engine are not counted: moving a message into
+    /// the NIC buffer of its destination and deferring an event while its
+    /// process is busy.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use std::time::Instant;
+    /// use dscale::SimulationBuilder;
+    /// # use dscale::{ProcessHandle, ProcessId, MessagePtr, TimerId};
+    /// # #[derive(Default)]
+    /// # struct MyProcess;
+    /// # impl ProcessHandle for MyProcess {
+    /// #     fn start(&mut self) {}
+    /// #     fn on_message(&mut self, from: ProcessId, message: MessagePtr) {}
+    /// #     fn on_timer(&mut self, id: TimerId) {}
+    /// # }
+    ///
+    /// let mut simulation = SimulationBuilder::default()
+    ///     .add_pool::<MyProcess>("nodes", 3)
+    ///     .build();
+    ///
+    /// let start = Instant::now();
+    /// simulation.run();
+    /// let rate = simulation.executed_events() as f64 / start.elapsed().as_secs_f64();
+    /// println!("{rate:.0} events/s");
+    /// ```
+    pub fn executed_events(&self) -> usize {
+        self.events
+    }
+
+    /// Collects statistics of the run so far.
+    ///
+    /// Reports executed events overall, per actor and per second of
+    /// wall-clock time, sent messages and bytes per message type, per pool
+    /// pair and per process, timers fired versus scheduled and peak sizes of
+    /// the event queues. [`run`](Self::run) logs the report at `info` level
+    /// when it finishes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use dscale::*;
+    ///
+    /// struct Ping;
+    ///
+    /// impl Message for Ping {
+    ///     fn virtual_size(&self) -> usize {
+    ///         64
+    ///     }
+    /// }
+    ///
+    /// #[derive(Default)]
+    /// struct Node;
+    ///
+    /// impl ProcessHandle for Node {
+    ///     fn start(&mut self) {
+    ///         schedule_timer_after(Jiffies(100));
+    ///     }
+    ///     fn on_message(&mut self, _from: ProcessId, _message: MessagePtr) {}
+    ///     fn on_timer(&mut self, _id: TimerId) {
+    ///         broadcast(Ping);
+    ///     }
+    /// }
+    ///
+    /// let mut simulation = SimulationBuilder::default()
+    ///     .add_pool::<Node>("nodes", 3)
+    ///     .latency_topology(&[LatencyDescription::WithinPool(
+    ///         "nodes",
+    ///         Distributions::Uniform(Jiffies(1), Jiffies(10)),
+    ///     )])
+    ///     .time_budget(Jiffies(1_000))
+    ///     .build();
+    /// simulation.run_until(Jiffies(1_000));
+    ///
+    /// let statistics = simulation.statistics();
+    /// assert_eq!(statistics.timers_fired, 3);
+    /// assert_eq!(statistics.events, 12);
+    /// assert_eq!(statistics.network_events, 9);
+    /// assert_eq!(statistics.timer_events, 3);
+    /// assert_eq!(statistics.by_type[std::any::type_name::<Ping>()].messages, 9);
+    /// assert_eq!(statistics.total_traffic().bytes, 9 * 64);
+    /// assert_eq!(statistics.processes[&1].received.messages, 3);
+    /// println!("{statistics}");
+    /// ```
+    pub fn statistics(&self) -> Statistics {
+        let mut statistics = Statistics {
+            events: self.events,
+            wall_clock: self.wall_clock,
+            network_events: self.actor_events[0],
+            timer_events: self.actor_events[1],
+            ..Default::default()
+        };
+        self.network.borrow().report(&mut statistics);
+        self.timers.borrow().report(&mut statistics);
+        statistics
+    }
+
+    /// Executes events due before the given time, then moves the clock to
+    /// it and returns.
+    ///
+    /// Processes are started on the first call. The simulation can be
+    /// inspected, [snapshotted](Self::snapshot) or run further afterwards;
+    /// [`run`](Self::run) continues up to the time budget. Faults injected in
+    /// between, e.g. with [`with_process`](Self::with_process), take effect at
+    /// `until` rather than at the time of the last executed event. Stops
+    /// short of the time budget if `until` is at or beyond it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use dscale::*;
+    ///
+    /// #[derive(Default)]
+    /// struct Ticker;
+    ///
+    /// impl ProcessHandle for Ticker {
+    ///     fn start(&mut self) {
+    ///         schedule_timer_after(Jiffies(100));
+    ///     }
+    ///     fn on_message(&mut self, _from: ProcessId, _message: MessagePtr) {}
+    ///     fn on_timer(&mut self, _id: TimerId) {
+    ///         schedule_timer_after(Jiffies(100));
+    ///     }
+    /// }
+    ///
+    /// let mut simulation = SimulationBuilder::default()
+    ///     .add_pool::<Ticker>("nodes", 1)
+    ///     .time_budget(Jiffies(1_000))
+    ///     .build();
+    ///
+    /// // The timer due at 500 is still pending, the last one fired at 400
+    /// simulation.run_until(Jiffies(500));
+    /// assert_eq!(now(), Jiffies(500));
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// * `until` - Events due at this time or later stay pending
+    ///
+    /// # Panics
+    ///
+    /// Panics if another simulation was built or forked on this thread since.
+    pub fn run_until(&mut self, until: Jiffies) {
+        self.ensure_active();
+        if !self.started {
+            self.start();
+        }
+        let until = until.min(self.time_budget);
+        let started = Instant::now();
+        while self
+            .peek_closest()
+            .is_some_and(|(future, _)| future < until)
+        {
             self.step();
         }
+        // Every pending event is due at `until` or later. The clock stays short of the
+        // budget, for run() to still tell a finished run from a deadlock.
+        if until < self.time_budget {
+            global::fast_forward_clock(until.max(global::now()));
+        }
+        self.wall_clock += started.elapsed();
+    }
 
-        // For small simulations progress bar is not fullfilling
-        self.progress_bar.finish();
+    /// Captures the complete state of the simulation at the current time.
+    ///
+    /// The snapshot holds copies of all processes, messages in flight,
+    /// pending timers and storage operations, random number generator
+    /// states and the clock. Any number of independent continuations can be
+    /// created from it with [`Snapshot::fork`], e.g. to inject a different
+    /// fault into each of them from the same warmed-up state.
+    ///
+    /// Only processes added with [`SimulationBuilder::add_cloneable_pool`]
+    /// can be copied. Values in [`anykv`](crate::global::anykv) belong to the
+    /// host and are not captured.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use dscale::*;
+    ///
+    /// #[derive(Clone, Default)]
+    /// struct Counter {
+    ///     ticks: usize,
+    /// }
+    ///
+    /// impl ProcessHandle for Counter {
+    ///     fn start(&mut self) {
+    ///         schedule_timer_after(Jiffies(10));
+    ///     }
+    ///     fn on_message(&mut self, _from: ProcessId, _message: MessagePtr) {}
+    ///     fn on_timer(&mut self, _id: TimerId) {
+    ///         self.ticks += 1;
+    ///         schedule_timer_after(Jiffies(10));
+    ///     }
+    /// }
+    ///
+    /// let mut simulation = SimulationBuilder::default()
+    ///     .add_cloneable_pool::<Counter>("nodes", 1)
+    ///     .time_budget(Jiffies(1_000))
+    ///     .build();
+    ///
+    /// simulation.run_until(Jiffies(500)); // Warm up once
+    /// let snapshot = simulation.snapshot();
+    /// drop(simulation);
+    ///
+    /// for reset in [false, true] {
+    ///     let mut fork = snapshot.fork();
+    ///     if reset {
+    ///         fork.with_process(1, |counter: &mut Counter| counter.ticks = 0);
+    ///     }
+    ///     fork.run();
+    ///     let ticks = fork.with_process(1, |counter: &mut Counter| counter.ticks);
+    ///     assert_eq!(ticks, if reset { 51 } else { 100 });
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if a process is not cloneable or if another simulation was
+    /// built or forked on this thread since.
+    ///
+    /// [`Snapshot::fork`]: crate::Snapshot::fork
+    /// [`SimulationBuilder::add_cloneable_pool`]: crate::SimulationBuilder::add_cloneable_pool
+    pub fn snapshot(&self) -> Snapshot {
+        self.ensure_active();
+        let nursery = self.nursery.fork();
+        Snapshot {
+            network: self.network.borrow().fork(nursery.clone()),
+            timers: self.timers.borrow().fork(nursery.clone()),
+            nursery,
+            topology: self.topology.clone(),
+            globals: global::save_all(),
+            access_random: global::save_access_random(),
+            seed: self.seed,
+            time_budget: self.time_budget,
+            events: self.events,
+            actor_events: self.actor_events.clone(),
+            wall_clock: self.wall_clock,
+        }
+    }
 
-        info!("Looks good! ヽ('ー`)ノ");
+    /// Runs a closure on a process in its context at the current time.
+    ///
+    /// The closure gets mutable access to the process and may use any
+    /// context function as a handler would: send messages, schedule timers,
+    /// [crash its storage](crate::storage::crash) and so on. Useful to inject
+    /// faults into a fork or to read process state after a run.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The process to run the closure on
+    /// * `f` - Closure receiving the process as its concrete type
+    ///
+    /// # Panics
+    ///
+    /// Panics if the process is not of type `P` or if another simulation was
+    /// built or forked on this thread since.
+    pub fn with_process<P: ProcessHandle, T>(
+        &mut self,
+        id: ProcessId,
+        f: impl FnOnce(&mut P) -> T,
+    ) -> T {
+        self.ensure_active();
+        let result = self.nursery.with_process(id, f);
+        global::schedule();
+        result
     }
 }
 
+/// How a simulation run ended, for drivers that must not exit the host process.
+pub(crate) enum RunOutcome {
+    Finished,
+    Deadlock,
+}
+
 impl Simulation {
+    pub(crate) fn run_to_outcome(&mut self) -> RunOutcome {
+        self.ensure_active();
+        if !self.started {
+            self.start();
+        }
+
+        let started = Instant::now();
+        while global::now() < self.time_budget {
+            if !self.step() {
+                self.wall_clock += started.elapsed();
+                self.progress_bar.finish();
+                return RunOutcome::Deadlock;
+            }
+        }
+        self.wall_clock += started.elapsed();
+
+        // For small simulations progress bar is not fullfilling
+        self.progress_bar.finish();
+
+        RunOutcome::Finished
+    }
+
+    // Controlled execution: the caller picks which enabled transition happens next.
+    // Logical clock advances by one jiffy per transition.
+    pub(crate) fn start_controlled(&mut self) {
+        self.network.borrow_mut().take_control();
+        if !self.started {
+            self.start();
+        }
+    }
+
+    pub(crate) fn enabled_transitions(&self) -> Vec<Transition> {
+        let mut enabled: Vec<Transition> = self
+            .network
+            .borrow()
+            .in_flight()
+            .into_iter()
+            .map(|(from, to, seq)| Transition::Deliver { from, to, seq })
+            .chain(
+                self.timers
+                    .borrow()
+                    .pending()
+                    .into_iter()
+                    .map(|(process, id)| Transition::Timer { process, id }),
+            )
+            .collect();
+        enabled.sort();
+        enabled
+    }
+
+    pub(crate) fn fire(&mut self, transition: Transition) {
+        global::fast_forward_clock(global::now() + Jiffies(1));
+        match transition {
+            Transition::Deliver { from, to, seq } => {
+                self.network.borrow_mut().deliver_controlled(from, to, seq)
+            }
+            Transition::Timer { process, id } => self.timers.borrow_mut().fire(process, id),
+        }
+        global::schedule();
+    }
+
+    fn ensure_active(&self) {
+        assert!(
+            global::is_active_simulation(self.id),
+            "Another simulation was built or forked on this thread since"
+        );
+    }
+
     fn start(&mut self) {
+        self.started = true;
         self.actors.iter_mut().for_each(|actor| {
             actor.borrow_mut().start();
             global::schedule(); // Only after start() to avoid double borrow_mut() of SharedActor
         });
     }
 
-    fn step(&mut self) {
+    // Returns false if there is nothing left to execute
+    fn step(&mut self) -> bool {
         match self.peek_closest() {
-            None => {
-                error!("DEADLOCK! (ﾉಥ益ಥ）ﾉ ┻━┻ Try with RUST_LOG=debug");
-                exit(1)
-            }
+            None => false,
             Some((future, actor)) => {
                 global::fast_forward_clock(future);
-                actor.borrow_mut().step();
+                let handled = self.actors[actor].borrow_mut().step();
                 global::schedule(); // Only after step() to avoid double borrow_mut() of SharedActor
+                if handled {
+                    self.events += 1;
+                    self.actor_events[actor] += 1;
+                }
                 self.progress_bar
                     .make_progress(future.min(self.time_budget));
+                true
             }
         }
     }
 
-    fn peek_closest(&mut self) -> Option<(Jiffies, SharedActor)> {
-        let mut min_time = Jiffies(usize::MAX);
-        let mut sha: Option<SharedActor> = None;
-        for actor in self.actors.iter() {
-            actor.borrow().peek_closest().map(|time| {
-                if time < min_time {
-                    min_time = time;
-                    sha = Some(actor.clone())
-                }
-            });
+    // Time of the next event and index of the actor executing it
+    fn peek_closest(&mut self) -> Option<(Jiffies, usize)> {
+        let mut closest: Option<((Jiffies, TieKey), usize)> = None;
+        for (idx, actor) in self.actors.iter().enumerate() {
+            if let Some(key) = actor.borrow().peek_closest()
+                && closest.as_ref().is_none_or(|(min_key, _)| key < *min_key)
+            {
+                closest = Some((key, idx))
+            }
         }
 
-        Some((min_time, sha?))
+        closest.map(|((time, _), actor)| (time, actor))
     }
 }
 
 impl Drop for Simulation {
     fn drop(&mut self) {
-        global::drop_all(); // Clear thread_locals
+        // Clear thread_locals unless they already belong to a newer simulation
+        if global::is_active_simulation(self.id) {
+            global::drop_all();
+        }
     }
 }
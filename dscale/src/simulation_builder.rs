@@ -8,14 +8,18 @@
 use std::{
     cell::RefCell,
     collections::{BTreeMap, HashMap},
+    time::Duration,
 };
 
 use crate::{
-    ProcessHandle, ProcessId, Simulation,
+    ClockSkew, ProcessHandle, ProcessId, Simulation,
+    global::TieBreaking,
     network::BandwidthDescription,
+    nursery::{self, Forker},
     process::UniqueProcessHandle,
-    random::Seed,
-    time::Jiffies,
+    random::{Distributions, Seed},
+    storage::StorageDescription,
+    time::{Jiffies, Time},
     topology::{LatencyDescription, LatencyTopology},
 };
 
@@ -72,10 +76,17 @@ fn init_logger() {
 /// ```
 pub struct SimulationBuilder {
     seed: Seed,
-    time_budget: Jiffies,
+    time_budget: Time,
+    jiffy: Duration,
+    tie_breaking: TieBreaking,
+    cores: BTreeMap<ProcessId, usize>,
+    clock_skews: BTreeMap<ProcessId, ClockSkew>,
+    storage: BTreeMap<ProcessId, StorageDescription>,
+    record_trace: bool,
     proc_id: usize,
     pools: HashMap<String, Vec<(ProcessId, UniqueProcessHandle)>>,
-    latency_topology: LatencyTopology,
+    forkers: BTreeMap<ProcessId, Forker>,
+    latency_topology: LatencyTopology<Time>,
     bandwidth: BandwidthDescription,
 }
 
@@ -83,11 +94,18 @@ impl Default for SimulationBuilder {
     fn default() -> Self {
         SimulationBuilder {
             seed: 69,
-            time_budget: Jiffies(1_000_000),
+            time_budget: Time::Jiffies(Jiffies(1_000_000)),
+            jiffy: Duration::from_millis(1),
+            tie_breaking: TieBreaking::default(),
+            cores: BTreeMap::new(),
+            clock_skews: BTreeMap::new(),
+            storage: BTreeMap::new(),
+            record_trace: false,
             proc_id: 1,
             pools: HashMap::new(),
+            forkers: BTreeMap::new(),
             bandwidth: BandwidthDescription::Unbounded,
-            latency_topology: HashMap::new(),
+            latency_topology: LatencyTopology::default(),
         }
     }
 }
@@ -160,6 +178,61 @@ impl SimulationBuilder {
         self
     }
 
+    /// Adds a pool of processes that can be copied by [`Simulation::snapshot`].
+    ///
+    /// Behaves exactly like [`add_pool`](Self::add_pool), but additionally
+    /// requires the process type to implement [`Clone`], so a snapshot can
+    /// capture the state of every process and each fork of it gets its own
+    /// copy. Pools added with [`add_pool`](Self::add_pool) can still be run,
+    /// but snapshotting a simulation containing them panics.
+    ///
+    /// # Type Parameters
+    ///
+    /// * `P` - The process type that implements [`ProcessHandle`] + [`Clone`] + [`Default`] + `'static`
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - A string identifier for the pool (used in topology configuration)
+    /// * `size` - The number of processes to create in this pool
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use dscale::{SimulationBuilder, ProcessHandle, ProcessId, MessagePtr, TimerId};
+    ///
+    /// #[derive(Clone, Default)]
+    /// struct Replica {
+    ///     log: Vec<u64>,
+    /// }
+    ///
+    /// impl ProcessHandle for Replica {
+    ///     fn start(&mut self) {}
+    ///     fn on_message(&mut self, from: ProcessId, message: MessagePtr) {}
+    ///     fn on_timer(&mut self, id: TimerId) {}
+    /// }
+    ///
+    /// let builder = SimulationBuilder::default().add_cloneable_pool::<Replica>("replicas", 3);
+    /// ```
+    ///
+    /// # Returns
+    ///
+    /// The `SimulationBuilder` instance for method chaining.
+    ///
+    /// [`Simulation::snapshot`]: crate::Simulation::snapshot
+    /// [`ProcessHandle`]: crate::ProcessHandle
+    pub fn add_cloneable_pool<P: ProcessHandle + Clone + Default + 'static>(
+        mut self,
+        name: &str,
+        size: usize,
+    ) -> SimulationBuilder {
+        let first = self.proc_id;
+        self = self.add_pool::<P>(name, size);
+        (first..self.proc_id).for_each(|id| {
+            self.forkers.insert(id, nursery::fork_process::<P>);
+        });
+        self
+    }
+
     /// Sets the random seed for deterministic simulation execution.
     ///
     /// The seed controls all random behavior in the simulation, including network
@@ -195,19 +268,26 @@ impl SimulationBuilder {
     ///
     /// The simulation will run until either the specified time budget is reached
     /// or a deadlock is detected (no more events to process). Time is measured
-    /// in [`Jiffies`], which are the basic unit of simulation time.
+    /// in [`Jiffies`], which are the basic unit of simulation time. A budget
+    /// given as a [`Duration`] is converted with the length of a jiffy when
+    /// the simulation is built, see [`jiffy`](Self::jiffy).
     ///
     /// # Arguments
     ///
-    /// * `time_budget` - The maximum simulation time as [`Jiffies`]
+    /// * `time_budget` - The maximum simulation time as [`Jiffies`] or [`Duration`]
     ///
     /// # Examples
     ///
     /// ```rust
+    /// use std::time::Duration;
+    ///
     /// use dscale::{SimulationBuilder, Jiffies};
     ///
     /// let builder = SimulationBuilder::default()
     ///     .time_budget(Jiffies(1_000_000));  // Run for 1 million time units
+    ///
+    /// let builder = SimulationBuilder::default()
+    ///     .time_budget(Duration::from_secs(60));  // 60_000 jiffies of 1ms
     /// ```
     ///
     /// # Returns
@@ -215,8 +295,58 @@ impl SimulationBuilder {
     /// The `SimulationBuilder` instance for method chaining.
     ///
     /// [`Jiffies`]: crate::Jiffies
-    pub fn time_budget(mut self, time_budget: Jiffies) -> Self {
-        self.time_budget = time_budget;
+    pub fn time_budget(mut self, time_budget: impl Into<Time>) -> Self {
+        self.time_budget = time_budget.into();
+        self
+    }
+
+    /// Declares the real-time length of one jiffy.
+    ///
+    /// Time in the simulation is counted in [`Jiffies`]; this declaration
+    /// gives them a meaning. Conversions between [`Jiffies`] and
+    /// [`Duration`] and bandwidths given as
+    /// [`BandwidthDescription::Mbps`] or [`BandwidthDescription::Gbps`] use
+    /// it, as do budgets and latencies handed to the builder as [`Duration`].
+    /// Everything given in [`Jiffies`] is unaffected.
+    ///
+    /// The declaration takes effect when the simulation is built, so the
+    /// builder methods may be called in any order. Until then
+    /// [`Jiffies::from`] still converts with the jiffy of the previous
+    /// simulation: pass durations to the builder as they are rather than
+    /// converting them.
+    ///
+    /// # Arguments
+    ///
+    /// * `jiffy` - Length of one jiffy, one millisecond by default
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    ///
+    /// use dscale::{BandwidthDescription, Jiffies, SimulationBuilder};
+    ///
+    /// let builder = SimulationBuilder::default()
+    ///     .jiffy(Duration::from_micros(10))
+    ///     .time_budget(Duration::from_secs(2)) // Jiffies(200_000)
+    ///     .nic_bandwidth(BandwidthDescription::Gbps(10)); // 12_500 bytes per jiffy
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `jiffy` is zero.
+    ///
+    /// # Returns
+    ///
+    /// The `SimulationBuilder` instance for method chaining.
+    ///
+    /// [`Jiffies`]: crate::Jiffies
+    /// [`Jiffies::from`]: crate::Jiffies#impl-From<Duration>-for-Jiffies
+    /// [`BandwidthDescription::Mbps`]: crate::BandwidthDescription::Mbps
+    /// [`BandwidthDescription::Gbps`]: crate::BandwidthDescription::Gbps
+    pub fn jiffy(mut self, jiffy: Duration) -> Self {
+        assert!(!jiffy.is_zero(), "A jiffy should last some time");
+        self.jiffy = jiffy;
         self
     }
 
@@ -238,6 +368,14 @@ impl SimulationBuilder {
     ///
     /// - [`LatencyDescription::WithinPool`] - Latency for messages between processes in the same pool
     /// - [`LatencyDescription::BetweenPools`] - Latency for messages between processes in different pools
+    /// - [`LatencyDescription::BetweenProcesses`] - Override for a single pair of processes
+    ///
+    /// Latencies are given in [`Jiffies`], or in real time as [`Duration`]
+    /// and then converted when the simulation is built, see [`jiffy`].
+    ///
+    /// Latencies are stored per pool pair, so the cost of this call does not
+    /// depend on pool sizes. A later description for the same pair replaces
+    /// an earlier one; process overrides always win over pool descriptions.
     ///
     /// # Distribution Types
     ///
@@ -281,55 +419,44 @@ impl SimulationBuilder {
     ///
     /// # Panics
     ///
-    /// Panics if a referenced pool name does not exist.
+    /// Panics if a referenced pool name or process does not exist.
     ///
     /// [`add_pool`]: Self::add_pool
+    /// [`jiffy`]: Self::jiffy
+    /// [`Jiffies`]: crate::Jiffies
     /// [`LatencyDescription`]: crate::LatencyDescription
     /// [`LatencyDescription::WithinPool`]: crate::LatencyDescription::WithinPool
     /// [`LatencyDescription::BetweenPools`]: crate::LatencyDescription::BetweenPools
+    /// [`LatencyDescription::BetweenProcesses`]: crate::LatencyDescription::BetweenProcesses
     /// [`Distributions::Uniform`]: crate::Distributions::Uniform
     /// [`Distributions::Normal`]: crate::Distributions::Normal
     /// [`Distributions::Bernoulli`]: crate::Distributions::Bernoulli
-    pub fn latency_topology(mut self, descriptions: &[LatencyDescription]) -> Self {
-        descriptions.iter().for_each(|d| {
-            let (from, to, distr) = match d {
-                LatencyDescription::WithinPool(name, distr) => (*name, *name, distr),
-                LatencyDescription::BetweenPools(pool_from, pool_to, distr) => {
-                    (*pool_from, *pool_to, distr)
-                }
-            };
-
-            let from_vec: Vec<ProcessId> = self
-                .pools
-                .get(from)
-                .expect("No pool found")
-                .iter()
-                .map(|(id, _)| *id)
-                .collect();
-
-            let to_vec: Vec<ProcessId> = self
-                .pools
-                .get(to)
-                .expect("No pool found")
-                .iter()
-                .map(|(id, _)| *id)
-                .collect();
-
-            let cartesian_product = from_vec
-                .iter()
-                .flat_map(|x| to_vec.iter().map(move |y| (*x, *y)));
-
-            let cartesian_product_backwards = from_vec
-                .iter()
-                .flat_map(|x| to_vec.iter().map(move |y| (*y, *x)));
-
-            cartesian_product.for_each(|key| {
-                self.latency_topology.insert(key, distr.clone());
-            });
-
-            cartesian_product_backwards.for_each(|key| {
-                self.latency_topology.insert(key, distr.clone());
-            });
+    pub fn latency_topology<T: Into<Time> + Copy>(
+        mut self,
+        descriptions: &[LatencyDescription<T>],
+    ) -> Self {
+        descriptions.iter().for_each(|d| match *d {
+            LatencyDescription::WithinPool(name, distr) => {
+                self.set_pool_latency(name, name, distr.map(Into::into));
+            }
+            LatencyDescription::BetweenPools(pool_from, pool_to, distr) => {
+                self.set_pool_latency(pool_from, pool_to, distr.map(Into::into));
+            }
+            LatencyDescription::BetweenProcesses(from, to, distr) => {
+                [from, to].into_iter().for_each(|id| {
+                    assert!(
+                        self.pools
+                            .values()
+                            .flatten()
+                            .any(|(member, _)| *member == id),
+                        "No process found"
+                    );
+                });
+                let distr = distr.map(Into::into);
+                let between_processes = &mut self.latency_topology.between_processes;
+                between_processes.insert((from, to), distr);
+                between_processes.insert((to, from), distr);
+            }
         });
         self
     }
@@ -352,6 +479,8 @@ impl SimulationBuilder {
     ///
     /// - [`BandwidthDescription::Unbounded`] - No bandwidth limitations
     /// - [`BandwidthDescription::Bounded(bytes_per_jiffy)`] - Limited to specified bytes per time unit
+    /// - [`BandwidthDescription::Mbps`], [`BandwidthDescription::Gbps`] - Limited to a
+    ///   real-time rate, see [`jiffy`](Self::jiffy)
     ///
     /// # Examples
     ///
@@ -380,12 +509,254 @@ impl SimulationBuilder {
     /// [`BandwidthDescription`]: crate::BandwidthDescription
     /// [`BandwidthDescription::Unbounded`]: crate::BandwidthDescription::Unbounded
     /// [`BandwidthDescription::Bounded`]: crate::BandwidthDescription::Bounded
+    /// [`BandwidthDescription::Mbps`]: crate::BandwidthDescription::Mbps
+    /// [`BandwidthDescription::Gbps`]: crate::BandwidthDescription::Gbps
     /// [`virtual_size()`]: crate::Message::virtual_size
     pub fn nic_bandwidth(mut self, bandwidth: BandwidthDescription) -> Self {
         self.bandwidth = bandwidth;
         self
     }
 
+    /// Sets the number of virtual cores of every process in a pool.
+    ///
+    /// Cores only matter for handlers that spend virtual CPU time through
+    /// [`consume_cpu`] or [`Message::cpu_cost`]. Each handler invocation
+    /// occupies one core for the CPU time it consumes, so a process with
+    /// several cores can overlap independent handlers in simulated time.
+    /// Deliveries and timers queue only once all cores are busy.
+    ///
+    /// Processes have a single core by default. The pool must already be added.
+    ///
+    /// # Arguments
+    ///
+    /// * `pool` - Name of a pool added with [`add_pool`]
+    /// * `cores` - Number of virtual cores, at least one
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use dscale::{SimulationBuilder, ProcessHandle, ProcessId, MessagePtr, TimerId};
+    ///
+    /// #[derive(Default)]
+    /// struct Validator;
+    ///
+    /// impl ProcessHandle for Validator {
+    ///     fn start(&mut self) {}
+    ///     fn on_message(&mut self, from: ProcessId, message: MessagePtr) {}
+    ///     fn on_timer(&mut self, id: TimerId) {}
+    /// }
+    ///
+    /// let builder = SimulationBuilder::default()
+    ///     .add_pool::<Validator>("single_threaded", 4)
+    ///     .add_pool::<Validator>("pipelined", 4)
+    ///     .cores("pipelined", 8); // Verifies up to 8 messages in parallel
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pool does not exist or `cores` is zero.
+    ///
+    /// # Returns
+    ///
+    /// The `SimulationBuilder` instance for method chaining.
+    ///
+    /// [`consume_cpu`]: crate::consume_cpu
+    /// [`Message::cpu_cost`]: crate::Message::cpu_cost
+    /// [`add_pool`]: SimulationBuilder::add_pool
+    pub fn cores(mut self, pool: &str, cores: usize) -> Self {
+        assert!(cores > 0, "Process should have at least one core");
+        self.pools
+            .get(pool)
+            .expect("No pool found")
+            .iter()
+            .for_each(|(id, _)| {
+                self.cores.insert(*id, cores);
+            });
+        self
+    }
+
+    /// Skews the local clocks of every process in a pool.
+    ///
+    /// Each process has a local clock, read with [`local_now`], which by
+    /// default shows global time. A skew makes it start `offset` jiffies away
+    /// from global time and run at rate `1 + drift`. Timers scheduled with
+    /// [`schedule_timer_after`] measure their delay on the local clock.
+    ///
+    /// Clocks can also be changed at runtime with [`set_clock_skew`]. The pool
+    /// must already be added.
+    ///
+    /// # Arguments
+    ///
+    /// * `pool` - Name of a pool added with [`add_pool`]
+    /// * `skew` - A [`ClockSkew`] applied to all processes in the pool
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use dscale::{SimulationBuilder, ClockSkew, ProcessHandle, ProcessId, MessagePtr, TimerId};
+    ///
+    /// #[derive(Default)]
+    /// struct Replica;
+    ///
+    /// impl ProcessHandle for Replica {
+    ///     fn start(&mut self) {}
+    ///     fn on_message(&mut self, from: ProcessId, message: MessagePtr) {}
+    ///     fn on_timer(&mut self, id: TimerId) {}
+    /// }
+    ///
+    /// let builder = SimulationBuilder::default()
+    ///     .add_pool::<Replica>("fast", 2)
+    ///     .add_pool::<Replica>("slow", 2)
+    ///     .clock_skew("fast", ClockSkew { offset: 30, drift: 0.02 })
+    ///     .clock_skew("slow", ClockSkew { offset: -30, drift: -0.02 });
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pool does not exist or `drift` is not above `-1`.
+    ///
+    /// # Returns
+    ///
+    /// The `SimulationBuilder` instance for method chaining.
+    ///
+    /// [`local_now`]: crate::local_now
+    /// [`schedule_timer_after`]: crate::schedule_timer_after
+    /// [`set_clock_skew`]: crate::set_clock_skew
+    /// [`ClockSkew`]: crate::ClockSkew
+    /// [`add_pool`]: SimulationBuilder::add_pool
+    pub fn clock_skew(mut self, pool: &str, skew: ClockSkew) -> Self {
+        assert!(skew.drift > -1.0, "Clock should not run backwards");
+        self.pools
+            .get(pool)
+            .expect("No pool found")
+            .iter()
+            .for_each(|(id, _)| {
+                self.clock_skews.insert(*id, skew);
+            });
+        self
+    }
+
+    /// Configures the virtual disk of every process in a pool.
+    ///
+    /// The description sets the latency distributions of reads, writes and
+    /// fsyncs and the disk throughput used by the [`storage`] module.
+    /// Processes without a configuration get a disk that completes every
+    /// operation instantly. The pool must already be added.
+    ///
+    /// # Arguments
+    ///
+    /// * `pool` - Name of a pool added with [`add_pool`]
+    /// * `description` - A [`StorageDescription`] of the disk
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use dscale::{SimulationBuilder, BandwidthDescription, Distributions, Jiffies};
+    /// use dscale::{ProcessHandle, ProcessId, MessagePtr, TimerId};
+    /// use dscale::storage::StorageDescription;
+    ///
+    /// #[derive(Default)]
+    /// struct Replica;
+    ///
+    /// impl ProcessHandle for Replica {
+    ///     fn start(&mut self) {}
+    ///     fn on_message(&mut self, from: ProcessId, message: MessagePtr) {}
+    ///     fn on_timer(&mut self, id: TimerId) {}
+    /// }
+    ///
+    /// let builder = SimulationBuilder::default()
+    ///     .add_pool::<Replica>("replicas", 3)
+    ///     .storage(
+    ///         "replicas",
+    ///         StorageDescription {
+    ///             fsync: Distributions::Uniform(Jiffies(2), Jiffies(10)),
+    ///             throughput: BandwidthDescription::Bounded(100_000),
+    ///             ..Default::default()
+    ///         },
+    ///     );
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pool does not exist.
+    ///
+    /// # Returns
+    ///
+    /// The `SimulationBuilder` instance for method chaining.
+    ///
+    /// [`storage`]: crate::storage
+    /// [`StorageDescription`]: crate::storage::StorageDescription
+    /// [`add_pool`]: SimulationBuilder::add_pool
+    pub fn storage(mut self, pool: &str, description: StorageDescription) -> Self {
+        self.pools
+            .get(pool)
+            .expect("No pool found")
+            .iter()
+            .for_each(|(id, _)| {
+                self.storage.insert(*id, description);
+            });
+        self
+    }
+
+    /// Sets how events scheduled for the same jiffy are ordered.
+    ///
+    /// By default simultaneous events (messages arriving and timers firing at
+    /// the same time) are executed in the order they were submitted, so a run
+    /// never depends on heap internals. [`TieBreaking::Randomized`] instead
+    /// orders them by a value derived from the seed, which helps to find bugs
+    /// that rely on a lucky order while keeping runs reproducible.
+    ///
+    /// # Arguments
+    ///
+    /// * `tie_breaking` - A [`TieBreaking`] policy
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use dscale::{SimulationBuilder, TieBreaking};
+    ///
+    /// let builder = SimulationBuilder::default()
+    ///     .seed(42)
+    ///     .tie_breaking(TieBreaking::Randomized);
+    /// ```
+    ///
+    /// # Returns
+    ///
+    /// The `SimulationBuilder` instance for method chaining.
+    ///
+    /// [`TieBreaking`]: crate::TieBreaking
+    /// [`TieBreaking::Randomized`]: crate::TieBreaking::Randomized
+    pub fn tie_breaking(mut self, tie_breaking: TieBreaking) -> Self {
+        self.tie_breaking = tie_breaking;
+        self
+    }
+
+    /// Records every handler invocation of the run.
+    ///
+    /// The recorded [`Trace`] is taken after the run with
+    /// [`Simulation::take_trace`] and can be exported, e.g. to the Chrome
+    /// trace-event format for Perfetto. Recording keeps one entry per handler
+    /// invocation in memory, so it is meant for short runs.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use dscale::SimulationBuilder;
+    ///
+    /// let builder = SimulationBuilder::default().record_trace();
+    /// ```
+    ///
+    /// # Returns
+    ///
+    /// The `SimulationBuilder` instance for method chaining.
+    ///
+    /// [`Trace`]: crate::trace::Trace
+    /// [`Simulation::take_trace`]: crate::Simulation::take_trace
+    pub fn record_trace(mut self) -> Self {
+        self.record_trace = true;
+        self
+    }
+
     /// Finalizes the configuration and builds the simulation.
     ///
     /// This method consumes the `SimulationBuilder` and creates a [`Simulation`]
@@ -437,11 +808,29 @@ impl SimulationBuilder {
 
         Simulation::new(
             self.seed,
-            self.time_budget,
+            self.time_budget.in_jiffies(self.jiffy),
+            self.jiffy,
+            self.tie_breaking,
+            self.cores,
+            self.clock_skews,
+            self.storage,
+            self.record_trace,
             self.bandwidth,
-            self.latency_topology,
+            self.latency_topology.in_jiffies(self.jiffy),
             pool_listing,
             procs,
+            self.forkers,
         )
     }
 }
+
+impl SimulationBuilder {
+    pub(crate) fn set_pool_latency(&mut self, from: &str, to: &str, distr: Distributions<Time>) {
+        [from, to].into_iter().for_each(|name| {
+            self.pools.get(name).expect("No pool found");
+        });
+        let between_pools = &mut self.latency_topology.between_pools;
+        between_pools.insert((from.to_string(), to.to_string()), distr);
+        between_pools.insert((to.to_string(), from.to_string()), distr);
+    }
+}
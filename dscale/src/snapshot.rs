@@ -0,0 +1,62 @@
+//! Captured state of a running simulation.
+//!
+//! A [`Snapshot`] is taken with [`Simulation::snapshot`] and can be forked
+//! into any number of independent simulations continuing from the same
+//! state, e.g. to explore many fault scenarios after a single expensive
+//! warm-up.
+
+use std::{rc::Rc, time::Duration};
+
+use crate::{
+    Simulation,
+    global::GlobalState,
+    network::Network,
+    nursery::Nursery,
+    random::{Randomizer, Seed},
+    time::{Jiffies, timer_manager::TimerManager},
+    topology::Topology,
+};
+
+/// Complete state of a simulation at some point in time.
+///
+/// Holds copies of all processes, messages in flight, pending timers and
+/// storage operations, random number generator states and the clock. The
+/// snapshot itself is never modified: every call to [`fork`](Self::fork)
+/// starts from exactly the same state, and forks continue bit-identically to
+/// the original simulation unless they are changed, e.g. with
+/// [`Simulation::with_process`].
+///
+/// See [`Simulation::snapshot`] for an example.
+pub struct Snapshot {
+    pub(crate) nursery: Rc<Nursery>,
+    pub(crate) network: Network,
+    pub(crate) timers: TimerManager,
+    pub(crate) topology: Rc<Topology>,
+    pub(crate) globals: GlobalState,
+    pub(crate) access_random: Randomizer,
+    pub(crate) seed: Seed,
+    pub(crate) time_budget: Jiffies,
+    pub(crate) events: usize,
+    pub(crate) actor_events: Vec<usize>,
+    pub(crate) wall_clock: Duration,
+}
+
+impl Snapshot {
+    /// Returns the simulation time the snapshot was taken at.
+    pub fn time(&self) -> Jiffies {
+        self.globals.clock
+    }
+
+    /// Creates a new simulation continuing from the snapshot.
+    ///
+    /// The fork has the same time budget as the original simulation and is
+    /// already started: [`Simulation::run`] resumes the event loop instead of
+    /// calling [`ProcessHandle::start`] again. As all engine state is
+    /// thread-local, only the most recently built or forked simulation of a
+    /// thread can be run; older ones panic when used and should be dropped.
+    ///
+    /// [`ProcessHandle::start`]: crate::ProcessHandle::start
+    pub fn fork(&self) -> Simulation {
+        Simulation::from_snapshot(self)
+    }
+}
@@ -0,0 +1,253 @@
+//! End-of-run statistics.
+//!
+//! The engine always counts executed events, sent and delivered messages and
+//! scheduled and fired timers. [`Simulation::statistics`] collects the counts
+//! into a [`Statistics`] report telling where a run spends its effort: which
+//! actor executes most events, which message types and pools dominate the
+//! traffic, and how large the queues of pending events grow. The report is
+//! also logged at `info` level when [`Simulation::run`] finishes.
+//!
+//! [`Simulation::statistics`]: crate::Simulation::statistics
+//! [`Simulation::run`]: crate::Simulation::run
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    time::Duration,
+};
+
+use crate::{ProcessId, topology::Topology};
+
+// Processes listed by the Display implementation
+const BUSIEST_PROCESSES: usize = 10;
+
+/// Number of messages and their total [`virtual_size`](crate::Message::virtual_size).
+///
+/// A broadcast counts once per target.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Traffic {
+    pub messages: usize,
+    pub bytes: usize,
+}
+
+impl Traffic {
+    fn add(&mut self, messages: usize, size: usize) {
+        self.messages += messages;
+        self.bytes += messages * size;
+    }
+}
+
+/// Traffic of a single process.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ProcessTraffic {
+    /// Messages sent by the process, counted when submitted to the network
+    pub sent: Traffic,
+    /// Messages delivered to the process
+    pub received: Traffic,
+}
+
+/// Summary of a run, see [`Simulation::statistics`].
+///
+/// `Display` renders a human-readable report listing the
+/// ten processes that sent the most bytes; every process is available in
+/// [`processes`](Self::processes).
+///
+/// [`Simulation::statistics`]: crate::Simulation::statistics
+#[derive(Clone, Debug, Default)]
+pub struct Statistics {
+    /// Executed events, see [`Simulation::executed_events`](crate::Simulation::executed_events)
+    pub events: usize,
+    /// Wall-clock time spent executing events
+    pub wall_clock: Duration,
+    /// Events executed by the network: messages delivered to their process
+    pub network_events: usize,
+    /// Events executed by the timer manager: fired timers and storage
+    /// completions
+    pub timer_events: usize,
+    /// Sent messages per [`Message::name`](crate::Message::name)
+    pub by_type: BTreeMap<&'static str, Traffic>,
+    /// Sent messages per `(source pool, destination pool)`, pairs without
+    /// traffic are omitted
+    pub between_pools: BTreeMap<(String, String), Traffic>,
+    pub processes: BTreeMap<ProcessId, ProcessTraffic>,
+    /// Scheduled timers, including completions of storage operations
+    pub timers_scheduled: usize,
+    /// Fired timers, including completions of storage operations
+    pub timers_fired: usize,
+    /// Largest number of messages sent but not yet delivered
+    pub peak_messages_in_flight: usize,
+    /// Largest number of timers scheduled but not yet fired
+    pub peak_pending_timers: usize,
+}
+
+impl Statistics {
+    /// Executed events per second of wall-clock time.
+    pub fn events_per_second(&self) -> f64 {
+        self.events as f64 / self.wall_clock.as_secs_f64().max(f64::EPSILON)
+    }
+
+    /// Total traffic over all message types.
+    pub fn total_traffic(&self) -> Traffic {
+        Traffic {
+            messages: self.by_type.values().map(|traffic| traffic.messages).sum(),
+            bytes: self.by_type.values().map(|traffic| traffic.bytes).sum(),
+        }
+    }
+}
+
+impl fmt::Display for Traffic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} messages, {} bytes", self.messages, self.bytes)
+    }
+}
+
+impl fmt::Display for Statistics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Events: {} in {:.2?} ({:.0} events/s), network {}, timers {}",
+            self.events,
+            self.wall_clock,
+            self.events_per_second(),
+            self.network_events,
+            self.timer_events,
+        )?;
+        writeln!(
+            f,
+            "Timers: {} fired of {} scheduled, peak {} pending",
+            self.timers_fired, self.timers_scheduled, self.peak_pending_timers,
+        )?;
+        writeln!(
+            f,
+            "Messages: {}, peak {} in flight",
+            self.total_traffic(),
+            self.peak_messages_in_flight,
+        )?;
+
+        writeln!(f, "By type:")?;
+        for (name, traffic) in &self.by_type {
+            writeln!(f, "  {name}: {traffic}")?;
+        }
+
+        writeln!(f, "By pool pair:")?;
+        for ((from, to), traffic) in &self.between_pools {
+            writeln!(f, "  {from} -> {to}: {traffic}")?;
+        }
+
+        let mut busiest: Vec<_> = self.processes.iter().collect();
+        busiest.sort_by_key(|(id, traffic)| (std::cmp::Reverse(traffic.sent.bytes), **id));
+        writeln!(f, "Busiest processes:")?;
+        for (id, traffic) in busiest.into_iter().take(BUSIEST_PROCESSES) {
+            writeln!(
+                f,
+                "  P{id}: sent {}, received {}",
+                traffic.sent, traffic.received
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// Message counters of the network. Pool pairs and processes are indexed the way
+// topology does it, so counting a message copy never hashes.
+#[derive(Clone)]
+pub(crate) struct NetworkCounters {
+    by_type: HashMap<&'static str, Traffic>,
+    // Row-major by pool index
+    between_pools: Vec<Traffic>,
+    // Indexed by ProcessId
+    processes: Vec<ProcessTraffic>,
+    in_flight: usize,
+    peak_in_flight: usize,
+}
+
+impl NetworkCounters {
+    pub(crate) fn new(topology: &Topology, procs: usize) -> Self {
+        Self {
+            by_type: HashMap::new(),
+            between_pools: vec![Traffic::default(); topology.pools_count().pow(2)],
+            processes: vec![ProcessTraffic::default(); procs + 1],
+            in_flight: 0,
+            peak_in_flight: 0,
+        }
+    }
+
+    pub(crate) fn sent(
+        &mut self,
+        topology: &Topology,
+        name: &'static str,
+        size: usize,
+        source: ProcessId,
+        targets: &[ProcessId],
+    ) {
+        self.by_type
+            .entry(name)
+            .or_default()
+            .add(targets.len(), size);
+        self.processes[source].sent.add(targets.len(), size);
+        let row = topology.pool_index(source) * topology.pools_count();
+        targets.iter().for_each(|target| {
+            self.between_pools[row + topology.pool_index(*target)].add(1, size);
+        });
+        self.in_flight += targets.len();
+        self.peak_in_flight = self.peak_in_flight.max(self.in_flight);
+    }
+
+    pub(crate) fn delivered(&mut self, dest: ProcessId, size: usize) {
+        self.processes[dest].received.add(1, size);
+        self.in_flight -= 1;
+    }
+
+    pub(crate) fn report(&self, topology: &Topology, statistics: &mut Statistics) {
+        statistics.by_type = self
+            .by_type
+            .iter()
+            .map(|(name, traffic)| (*name, *traffic))
+            .collect();
+
+        let names = topology.pool_names();
+        statistics.between_pools = self
+            .between_pools
+            .iter()
+            .enumerate()
+            .filter(|(_, traffic)| traffic.messages > 0)
+            .map(|(idx, traffic)| {
+                let (from, to) = (idx / names.len(), idx % names.len());
+                ((names[from].to_string(), names[to].to_string()), *traffic)
+            })
+            .collect();
+
+        statistics.processes = self
+            .processes
+            .iter()
+            .enumerate()
+            .skip(1) // ProcessIds start from 1
+            .map(|(id, traffic)| (id, *traffic))
+            .collect();
+        statistics.peak_messages_in_flight = self.peak_in_flight;
+    }
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct TimerCounters {
+    scheduled: usize,
+    fired: usize,
+    peak_pending: usize,
+}
+
+impl TimerCounters {
+    pub(crate) fn scheduled(&mut self) {
+        self.scheduled += 1;
+        self.peak_pending = self.peak_pending.max(self.scheduled - self.fired);
+    }
+
+    pub(crate) fn fired(&mut self) {
+        self.fired += 1;
+    }
+
+    pub(crate) fn report(&self, statistics: &mut Statistics) {
+        statistics.timers_scheduled = self.scheduled;
+        statistics.timers_fired = self.fired;
+        statistics.peak_pending_timers = self.peak_pending;
+    }
+}
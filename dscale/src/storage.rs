@@ -0,0 +1,318 @@
+//! Simulated durable storage with fsync and crash semantics.
+//!
+//! Every process owns a key-value store backed by a virtual disk. Operations
+//! are asynchronous: [`write`], [`read`] and [`fsync`] return an
+//! [`OperationId`] right away and the result is delivered later to
+//! [`ProcessHandle::on_storage`] as a [`Completion`], like a timer firing.
+//!
+//! - Writes land in a volatile page cache and are visible to later reads at once.
+//! - [`fsync`] makes every write issued before it durable when it completes.
+//! - [`crash`] throws away the page cache and all operations still in flight,
+//!   leaving only what was synced.
+//!
+//! Latency of each operation is sampled from a [`StorageDescription`] configured
+//! with [`SimulationBuilder::storage`]. Writes and reads also transfer their
+//! size through the disk, which serves one transfer at a time at the configured
+//! throughput. Processes without a configuration get an instant disk.
+//!
+//! The engine does not restart processes, so a crash only affects storage:
+//! the scenario is responsible for also resetting the in-memory state of the
+//! crashed process.
+//!
+//! [`ProcessHandle::on_storage`]: crate::ProcessHandle::on_storage
+//! [`SimulationBuilder::storage`]: crate::SimulationBuilder::storage
+
+use std::{
+    any::Any,
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    rc::Rc,
+};
+
+use log::debug;
+
+use crate::{
+    BandwidthDescription, Distributions, Jiffies, ProcessId,
+    global::{self, schedule_completion},
+    now,
+    random::{Randomizer, Seed},
+    rank,
+};
+
+// Decorrelates disk latencies from the network stream that uses the same base seed
+const STORAGE_SALT: Seed = 0x5107_A6E0_D15C_0000;
+
+/// Identifier of a storage operation, echoed back in its [`Completion`].
+pub type OperationId = usize;
+
+/// A value kept in storage.
+pub type StoredValue = Rc<dyn Any>;
+
+/// Performance model of the virtual disk of a process.
+///
+/// # Examples
+///
+/// ```rust
+/// use dscale::{BandwidthDescription, Distributions, Jiffies};
+/// use dscale::storage::StorageDescription;
+///
+/// // SSD-like disk: fast reads and writes, fsync dominates
+/// let ssd = StorageDescription {
+///     read: Distributions::Uniform(Jiffies(0), Jiffies(1)),
+///     write: Distributions::Uniform(Jiffies(0), Jiffies(1)),
+///     fsync: Distributions::Normal(Jiffies(5), Jiffies(1)),
+///     throughput: BandwidthDescription::Bounded(500_000), // Bytes per jiffy
+/// };
+/// ```
+#[derive(Clone, Copy)]
+pub struct StorageDescription {
+    pub read: Distributions,
+    pub write: Distributions,
+    pub fsync: Distributions,
+    pub throughput: BandwidthDescription,
+}
+
+impl Default for StorageDescription {
+    fn default() -> Self {
+        let instant = Distributions::Uniform(Jiffies(0), Jiffies(0));
+        Self {
+            read: instant,
+            write: instant,
+            fsync: instant,
+            throughput: BandwidthDescription::Unbounded,
+        }
+    }
+}
+
+/// Result of a storage operation, delivered to [`ProcessHandle::on_storage`].
+///
+/// [`ProcessHandle::on_storage`]: crate::ProcessHandle::on_storage
+pub enum Completion {
+    /// The write reached the page cache.
+    Written(OperationId),
+    /// The value of the key at completion time, if any.
+    Read(OperationId, Option<StoredValue>),
+    /// All writes issued before the fsync are durable.
+    Synced(OperationId),
+}
+
+impl Completion {
+    /// Identifier returned by the call that started the operation.
+    pub fn id(&self) -> OperationId {
+        match self {
+            Completion::Written(id) | Completion::Read(id, _) | Completion::Synced(id) => *id,
+        }
+    }
+}
+
+#[derive(Clone)]
+enum Pending {
+    Write,
+    Read(String),
+    Sync(BTreeMap<String, StoredValue>),
+}
+
+#[derive(Clone, Default)]
+struct Disk {
+    description: StorageDescription,
+    volatile: BTreeMap<String, StoredValue>,
+    durable: BTreeMap<String, StoredValue>,
+    transferred_until: Jiffies,
+    // Fsyncs complete in issue order
+    synced_at: Jiffies,
+    // Bumped on every crash, older operations never complete
+    epoch: usize,
+}
+
+impl Disk {
+    // Time at which a transfer of `size` bytes started now leaves the disk
+    fn transfer(&mut self, size: usize) -> Jiffies {
+        let duration = match self.description.throughput.bytes_per_jiffy() {
+            None => 0,
+            Some(bytes_per_jiffy) => size.div_ceil(bytes_per_jiffy),
+        };
+        self.transferred_until = self.transferred_until.max(now()) + Jiffies(duration);
+        self.transferred_until
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Storage {
+    randomizer: Randomizer,
+    disks: BTreeMap<ProcessId, Disk>,
+    in_flight: HashMap<OperationId, (ProcessId, usize, Pending)>,
+}
+
+impl Storage {
+    fn disk(&mut self, id: ProcessId) -> &mut Disk {
+        self.disks.entry(id).or_default()
+    }
+
+    fn sample(&mut self, latency: Distributions) -> Jiffies {
+        Jiffies(self.randomizer.random_usize(latency))
+    }
+
+    fn submit(
+        &mut self,
+        process: ProcessId,
+        pending: Pending,
+        complete_at: Jiffies,
+    ) -> OperationId {
+        let id = global::global_unique_id();
+        let epoch = self.disk(process).epoch;
+        debug!("Storage operation {id} of P{process} completes at {complete_at}");
+        self.in_flight.insert(id, (process, epoch, pending));
+        schedule_completion(id, complete_at.saturating_sub(now()));
+        id
+    }
+}
+
+thread_local! {
+    static STORAGE: RefCell<Option<Storage>> = const { RefCell::new(None) };
+}
+
+fn with_storage<T>(f: impl FnOnce(&mut Storage) -> T) -> T {
+    STORAGE.with_borrow_mut(|storage| f(storage.as_mut().expect("Out of simulation context")))
+}
+
+pub(crate) fn setup_storage(descriptions: &BTreeMap<ProcessId, StorageDescription>, seed: Seed) {
+    STORAGE.set(Some(Storage {
+        randomizer: Randomizer::new(seed ^ STORAGE_SALT),
+        disks: descriptions
+            .iter()
+            .map(|(id, description)| {
+                (
+                    *id,
+                    Disk {
+                        description: *description,
+                        ..Default::default()
+                    },
+                )
+            })
+            .collect(),
+        in_flight: HashMap::new(),
+    }));
+}
+
+pub(crate) fn save_storage() -> Option<Storage> {
+    STORAGE.with_borrow(|storage| storage.clone())
+}
+
+pub(crate) fn restore_storage(storage: Option<Storage>) {
+    STORAGE.set(storage);
+}
+
+pub(crate) fn drop_storage() {
+    STORAGE.take();
+}
+
+// None if the id does not belong to a storage operation (it is a plain timer).
+// Some(None) if the operation was lost in a crash.
+pub(crate) fn take_completion(id: OperationId) -> Option<Option<Completion>> {
+    STORAGE.with_borrow_mut(|storage| {
+        let storage = storage.as_mut()?;
+        let (process, epoch, pending) = storage.in_flight.remove(&id)?;
+        let disk = storage.disk(process);
+        if epoch != disk.epoch {
+            return Some(None);
+        }
+        Some(Some(match pending {
+            Pending::Write => Completion::Written(id),
+            Pending::Read(key) => Completion::Read(id, disk.volatile.get(&key).cloned()),
+            Pending::Sync(snapshot) => {
+                disk.durable.extend(snapshot);
+                Completion::Synced(id)
+            }
+        }))
+    })
+}
+
+/// Writes a value into the page cache of the current process.
+///
+/// The value is visible to reads immediately, but survives a [`crash`] only
+/// after a later [`fsync`] completes. `size` is the number of bytes
+/// transferred through the disk.
+///
+/// # Examples
+///
+/// ```rust
+/// use dscale::storage::{self, Completion};
+/// use dscale::{MessagePtr, ProcessHandle, ProcessId, TimerId};
+///
+/// #[derive(Default)]
+/// struct Replica {
+///     pending_sync: Option<storage::OperationId>,
+/// }
+///
+/// impl ProcessHandle for Replica {
+///     fn start(&mut self) {
+///         storage::write("ballot", 7u64, 8);
+///         self.pending_sync = Some(storage::fsync());
+///     }
+///
+///     fn on_message(&mut self, _from: ProcessId, _message: MessagePtr) {}
+///     fn on_timer(&mut self, _id: TimerId) {}
+///
+///     fn on_storage(&mut self, completion: Completion) {
+///         if Some(completion.id()) == self.pending_sync {
+///             // Ballot is durable, safe to reply
+///         }
+///     }
+/// }
+/// ```
+pub fn write<T: Any>(key: &str, value: T, size: usize) -> OperationId {
+    let process = rank();
+    with_storage(|storage| {
+        let disk = storage.disk(process);
+        disk.volatile.insert(key.to_string(), Rc::new(value));
+        let ready = disk.transfer(size);
+        let latency = disk.description.write;
+        let complete_at = ready + storage.sample(latency);
+        storage.submit(process, Pending::Write, complete_at)
+    })
+}
+
+/// Reads the value of a key of the current process.
+///
+/// The value is taken from the page cache when the operation completes and
+/// delivered as [`Completion::Read`]. `size` is the number of bytes
+/// transferred through the disk.
+pub fn read(key: &str, size: usize) -> OperationId {
+    let process = rank();
+    with_storage(|storage| {
+        let disk = storage.disk(process);
+        let ready = disk.transfer(size);
+        let latency = disk.description.read;
+        let complete_at = ready + storage.sample(latency);
+        storage.submit(process, Pending::Read(key.to_string()), complete_at)
+    })
+}
+
+/// Makes all writes issued so far by the current process durable.
+pub fn fsync() -> OperationId {
+    let process = rank();
+    with_storage(|storage| {
+        let latency = storage.disk(process).description.fsync;
+        let sampled = storage.sample(latency);
+        let disk = storage.disk(process);
+        let snapshot = disk.volatile.clone();
+        let complete_at = (disk.transferred_until.max(now()) + sampled).max(disk.synced_at);
+        disk.synced_at = complete_at;
+        storage.submit(process, Pending::Sync(snapshot), complete_at)
+    })
+}
+
+/// Crashes the disk of a process.
+///
+/// Unsynced writes are lost and operations in flight never complete. Can be
+/// called from any process, for example a nemesis.
+pub fn crash(process: ProcessId) {
+    debug!("Crashing storage of P{process}");
+    with_storage(|storage| {
+        let disk = storage.disk(process);
+        disk.epoch += 1;
+        disk.volatile = disk.durable.clone();
+        disk.transferred_until = now();
+        disk.synced_at = now();
+    });
+}
@@ -6,9 +6,12 @@
 
 use std::{
     fmt::{Debug, Display},
-    ops::{Add, AddAssign, Mul, Sub},
+    ops::{Add, AddAssign, Mul},
+    time::Duration,
 };
 
+use crate::global::jiffy;
+
 /// A discrete unit of simulation time in DScale.
 ///
 /// `Jiffies` represents time as discrete, integer-based units rather than
@@ -16,9 +19,11 @@ use std::{
 /// simulation runs and eliminates floating-point precision issues that
 /// could lead to non-reproducible results.
 ///
-/// The actual duration represented by one jiffy is abstract and depends on
-/// your simulation's context. You can think of jiffies as milliseconds,
-/// microseconds, or any other time unit that makes sense for your system.
+/// The real-time length of one jiffy is declared with
+/// [`SimulationBuilder::jiffy`] and defaults to one millisecond. Once the
+/// simulation is built, jiffies convert to and from [`Duration`] using it, so
+/// configuration can be written in real time units (see [`Time`]) and stays
+/// correct when the resolution changes.
 ///
 /// # Design Philosophy
 ///
@@ -48,8 +53,9 @@ use std::{
 ///
 /// // Arithmetic operations
 /// let total_time = delay + timeout;  // Jiffies(5100)
-/// let doubled = delay * 2;           // Not directly supported, use usize * Jiffies
-/// let remaining = timeout - delay;   // Jiffies(4900)
+/// let doubled = 2 * delay;           // usize * Jiffies yields usize (200)
+/// let remaining = timeout.checked_sub(delay);  // Some(Jiffies(4900))
+/// let overdue = delay.saturating_sub(timeout);  // Jiffies(0)
 ///
 /// // In a process context
 /// fn schedule_work() {
@@ -72,6 +78,33 @@ use std::{
 /// println!("{}", time);    // Prints: "Jiffies(12345)"
 /// println!("{:?}", time);  // Prints: "12345"
 /// ```
+///
+/// Conversions to and from real time use the jiffy of the current
+/// simulation, so they are only available once it is built:
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use dscale::{Jiffies, SimulationBuilder};
+///
+/// let _simulation = SimulationBuilder::default()
+///     .jiffy(Duration::from_micros(100))
+///     .build();
+///
+/// assert_eq!(Jiffies::from_duration(Duration::from_millis(5)), Jiffies(50));
+/// assert_eq!(Jiffies(25).as_duration(), Duration::from_micros(2_500));
+/// ```
+///
+/// ```rust,should_panic
+/// use std::time::Duration;
+///
+/// use dscale::Jiffies;
+///
+/// // No simulation, so no jiffy to convert with
+/// Jiffies::from_duration(Duration::from_millis(5));
+/// ```
+///
+/// [`SimulationBuilder::jiffy`]: crate::SimulationBuilder::jiffy
 #[derive(PartialEq, PartialOrd, Ord, Eq, Copy, Clone, Default)]
 pub struct Jiffies(pub usize);
 
@@ -83,11 +116,112 @@ impl Add for Jiffies {
     }
 }
 
-impl Sub for Jiffies {
-    type Output = Jiffies;
+impl Jiffies {
+    /// Converts a real-time duration, rounding to the nearest jiffy.
+    ///
+    /// Uses the length of a jiffy of the current simulation, see
+    /// [`jiffy`](crate::global::jiffy).
+    ///
+    /// To configure a [`SimulationBuilder`] in real time, hand it the
+    /// [`Duration`] itself: the builder converts it with its own jiffy when
+    /// the simulation is built.
+    ///
+    /// [`SimulationBuilder`]: crate::SimulationBuilder
+    ///
+    /// # Panics
+    ///
+    /// Panics if no simulation exists on this thread.
+    pub fn from_duration(duration: Duration) -> Self {
+        Self::from_duration_in(duration, jiffy())
+    }
+
+    pub(crate) fn from_duration_in(duration: Duration, jiffy: Duration) -> Self {
+        let jiffy = jiffy.as_nanos().max(1);
+        Jiffies(((duration.as_nanos() + jiffy / 2) / jiffy) as usize)
+    }
+
+    /// Converts to a real-time duration.
+    ///
+    /// Uses the length of a jiffy of the current simulation, see
+    /// [`jiffy`](crate::global::jiffy).
+    ///
+    /// # Panics
+    ///
+    /// Panics if no simulation exists on this thread, or if the duration
+    /// does not fit in [`Duration`], that is beyond about 584 billion years.
+    pub fn as_duration(self) -> Duration {
+        let nanos = jiffy().as_nanos() * self.0 as u128;
+        let secs = u64::try_from(nanos / 1_000_000_000).expect("Duration overflow");
+        Duration::new(secs, (nanos % 1_000_000_000) as u32)
+    }
+
+    /// `self + rhs`, or `None` on overflow.
+    pub fn checked_add(self, rhs: Jiffies) -> Option<Jiffies> {
+        self.0.checked_add(rhs.0).map(Jiffies)
+    }
+
+    /// `self - rhs`, or `None` if `rhs` is later than `self`.
+    ///
+    /// Jiffies do not implement `Sub`: subtracting a later time is a bug
+    /// that should be handled where it happens.
+    pub fn checked_sub(self, rhs: Jiffies) -> Option<Jiffies> {
+        self.0.checked_sub(rhs.0).map(Jiffies)
+    }
+
+    /// `self - rhs`, or zero if `rhs` is later than `self`.
+    pub fn saturating_sub(self, rhs: Jiffies) -> Jiffies {
+        Jiffies(self.0.saturating_sub(rhs.0))
+    }
+}
+
+/// Simulation time given either in jiffies or in real time.
+///
+/// [`SimulationBuilder`] accepts both wherever it takes a time budget or a
+/// latency, and converts real time with the length of a jiffy declared by
+/// [`SimulationBuilder::jiffy`] when the simulation is built, whichever order
+/// the builder methods are called in.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use dscale::{Jiffies, time::Time};
+///
+/// assert_eq!(Time::from(Jiffies(5)), Time::Jiffies(Jiffies(5)));
+/// assert_eq!(
+///     Time::from(Duration::from_millis(5)),
+///     Time::Real(Duration::from_millis(5))
+/// );
+/// ```
+///
+/// [`SimulationBuilder`]: crate::SimulationBuilder
+/// [`SimulationBuilder::jiffy`]: crate::SimulationBuilder::jiffy
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Time {
+    Jiffies(Jiffies),
+    Real(Duration),
+}
+
+impl Time {
+    // Rounds real time to the nearest jiffy of the given length
+    pub(crate) fn in_jiffies(self, jiffy: Duration) -> Jiffies {
+        match self {
+            Time::Jiffies(jiffies) => jiffies,
+            Time::Real(duration) => Jiffies::from_duration_in(duration, jiffy),
+        }
+    }
+}
+
+impl From<Jiffies> for Time {
+    fn from(jiffies: Jiffies) -> Self {
+        Time::Jiffies(jiffies)
+    }
+}
 
-    fn sub(self, rhs: Self) -> Self::Output {
-        Jiffies(self.0 - rhs.0)
+impl From<Duration> for Time {
+    fn from(duration: Duration) -> Self {
+        Time::Real(duration)
     }
 }
 
@@ -2,4 +2,5 @@ pub mod jiffy;
 pub mod timer_manager;
 
 pub use jiffy::Jiffies;
+pub use jiffy::Time;
 pub use timer_manager::TimerId;
@@ -12,8 +12,10 @@ use crate::{
     ProcessId,
     actor::{EventSubmitter, SimulationActor},
     communication::DScaleMessage,
-    global, now,
+    global::{self, TieKey, cpu},
+    now,
     nursery::Nursery,
+    stats::{Statistics, TimerCounters},
     time::Jiffies,
 };
 
@@ -89,9 +91,12 @@ pub(crate) fn next_timer_id() -> TimerId {
 
 pub(crate) type TimerManagerActor = Rc<RefCell<TimerManager>>;
 
+type TimerEntry = (Jiffies, TieKey, (ProcessId, TimerId));
+
 pub(crate) struct TimerManager {
-    working_timers: BinaryHeap<Reverse<(Jiffies, (ProcessId, TimerId))>>,
+    working_timers: BinaryHeap<Reverse<TimerEntry>>,
     nursery: Rc<Nursery>,
+    counters: TimerCounters,
 }
 
 impl TimerManager {
@@ -99,24 +104,64 @@ impl TimerManager {
         Self {
             working_timers: BinaryHeap::new(),
             nursery,
+            counters: TimerCounters::default(),
         }
     }
 }
 
+impl TimerManager {
+    pub(crate) fn fork(&self, nursery: Rc<Nursery>) -> Self {
+        Self {
+            working_timers: self.working_timers.clone(),
+            nursery,
+            counters: self.counters.clone(),
+        }
+    }
+
+    pub(crate) fn report(&self, statistics: &mut Statistics) {
+        self.counters.report(statistics);
+    }
+
+    pub(crate) fn pending(&self) -> Vec<(ProcessId, TimerId)> {
+        self.working_timers.iter().map(|entry| entry.0.2).collect()
+    }
+
+    // Fires a specific timer regardless of its deadline
+    pub(crate) fn fire(&mut self, process_id: ProcessId, timer_id: TimerId) {
+        self.working_timers
+            .retain(|entry| entry.0.2 != (process_id, timer_id));
+        debug!("Firing timer with TimerId {timer_id} for P{process_id} out of order");
+        self.counters.fired();
+        self.nursery
+            .deliver(process_id, process_id, DScaleMessage::Timer(timer_id));
+    }
+}
+
 impl SimulationActor for TimerManager {
     fn start(&mut self) {
         // Do nothing
     }
 
-    fn peek_closest(&self) -> Option<Jiffies> {
-        self.working_timers.peek().map(|entry| entry.0.0)
+    fn peek_closest(&self) -> Option<(Jiffies, TieKey)> {
+        self.working_timers
+            .peek()
+            .map(|entry| (entry.0.0, entry.0.1))
     }
 
-    fn step(&mut self) {
-        let (_, (process_id, timer_id)) = self.working_timers.pop().expect("Should not be empty").0;
+    fn step(&mut self) -> bool {
+        let (_, tie, (process_id, timer_id)) =
+            self.working_timers.pop().expect("Should not be empty").0;
+        if let Some(until) = cpu::busy_until(process_id) {
+            debug!("P{process_id} is busy, deferring TimerId {timer_id} until {until}");
+            self.working_timers
+                .push(Reverse((until, tie, (process_id, timer_id))));
+            return false;
+        }
         debug!("Firing timer with TimerId {timer_id} for P{process_id}");
+        self.counters.fired();
         self.nursery
             .deliver(process_id, process_id, DScaleMessage::Timer(timer_id));
+        true
     }
 }
 
@@ -125,8 +170,12 @@ impl EventSubmitter for TimerManager {
 
     fn submit(&mut self, events: &mut Vec<Self::Event>) {
         events.drain(..).for_each(|(source, timer_id, after)| {
-            self.working_timers
-                .push(Reverse((now() + after, (source, timer_id))));
+            self.counters.scheduled();
+            self.working_timers.push(Reverse((
+                now() + after,
+                global::next_tie_key(),
+                (source, timer_id),
+            )));
         });
     }
 }
@@ -5,13 +5,47 @@
 //! modeling different latency patterns within process pools and between
 //! different pools to create realistic network topologies.
 
-use std::{collections::HashMap, rc::Rc};
+use std::{collections::HashMap, rc::Rc, time::Duration};
 
-use crate::{ProcessId, random::Distributions};
+use crate::{Jiffies, ProcessId, random::Distributions, time::Time};
 
-pub(crate) type LatencyTopology = HashMap<(ProcessId, ProcessId), Distributions>;
 pub(crate) type PoolListing = HashMap<String, Vec<ProcessId>>;
 
+// Latencies as configured: per pool pair, plus per process pair overrides.
+// Both are stored in both directions. The builder keeps them in `Time` until
+// the length of a jiffy is known.
+pub(crate) struct LatencyTopology<T = Jiffies> {
+    pub(crate) between_pools: HashMap<(String, String), Distributions<T>>,
+    pub(crate) between_processes: HashMap<(ProcessId, ProcessId), Distributions<T>>,
+}
+
+impl<T> Default for LatencyTopology<T> {
+    fn default() -> Self {
+        Self {
+            between_pools: HashMap::new(),
+            between_processes: HashMap::new(),
+        }
+    }
+}
+
+impl LatencyTopology<Time> {
+    pub(crate) fn in_jiffies(self, jiffy: Duration) -> LatencyTopology {
+        let convert = |distr: Distributions<Time>| distr.map(|time| time.in_jiffies(jiffy));
+        LatencyTopology {
+            between_pools: self
+                .between_pools
+                .into_iter()
+                .map(|(pools, distr)| (pools, convert(distr)))
+                .collect(),
+            between_processes: self
+                .between_processes
+                .into_iter()
+                .map(|(procs, distr)| (procs, convert(distr)))
+                .collect(),
+        }
+    }
+}
+
 /// Describes network latency characteristics for different process relationships.
 ///
 /// `LatencyDescription` allows you to configure different latency patterns
@@ -27,7 +61,8 @@ pub(crate) type PoolListing = HashMap<String, Vec<ProcessId>>;
 ///
 /// Each relationship can be configured with different probability distributions
 /// to model various network characteristics like jitter, packet loss, and
-/// varying network conditions.
+/// varying network conditions. Latencies are in [`Jiffies`] by default, or in
+/// real time with `LatencyDescription<Duration>`.
 ///
 /// # Usage in Simulation Configuration
 ///
@@ -73,7 +108,7 @@ pub(crate) type PoolListing = HashMap<String, Vec<ProcessId>>;
 /// ```
 ///
 /// [`SimulationBuilder::latency_topology`]: crate::SimulationBuilder::latency_topology
-pub enum LatencyDescription {
+pub enum LatencyDescription<T = Jiffies> {
     /// Configures latency for messages within a single process pool.
     ///
     /// This variant specifies the latency characteristics for communication
@@ -124,7 +159,7 @@ pub enum LatencyDescription {
     ///
     /// [`add_pool`]: crate::SimulationBuilder::add_pool
     /// [`Distributions`]: crate::Distributions
-    WithinPool(&'static str, Distributions),
+    WithinPool(&'static str, Distributions<T>),
 
     /// Configures latency for messages between two different process pools.
     ///
@@ -182,12 +217,45 @@ pub enum LatencyDescription {
     /// ```
     ///
     /// [`Distributions`]: crate::Distributions
-    BetweenPools(&'static str, &'static str, Distributions),
+    BetweenPools(&'static str, &'static str, Distributions<T>),
+
+    /// Overrides latency for messages between two specific processes.
+    ///
+    /// Takes precedence over the pool-level descriptions of both processes,
+    /// regardless of the order in which descriptions are given. Like
+    /// [`BetweenPools`], it applies in both directions.
+    ///
+    /// # Parameters
+    ///
+    /// * [`ProcessId`] - The first process
+    /// * [`ProcessId`] - The second process
+    /// * [`Distributions`] - The probability distribution for latency values
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use dscale::{LatencyDescription, Distributions, Jiffies};
+    ///
+    /// // A single replica behind a slow link
+    /// let slow_link = LatencyDescription::BetweenProcesses(1, 4,
+    ///     Distributions::Uniform(Jiffies(200), Jiffies(300))
+    /// );
+    /// ```
+    ///
+    /// [`BetweenPools`]: LatencyDescription::BetweenPools
+    /// [`ProcessId`]: crate::ProcessId
+    /// [`Distributions`]: crate::Distributions
+    BetweenProcesses(ProcessId, ProcessId, Distributions<T>),
 }
 
 pub(crate) struct Topology {
     pool_listing: PoolListing,
-    latency_topology: LatencyTopology,
+    // Index of the pool of every process, indexed by ProcessId
+    pool_of: Vec<usize>,
+    pools_count: usize,
+    // Pool pair latencies, row-major by pool index
+    between_pools: Vec<Option<Distributions>>,
+    between_processes: HashMap<(ProcessId, ProcessId), Distributions>,
 }
 
 impl Topology {
@@ -195,20 +263,76 @@ impl Topology {
         pool_listing: PoolListing,
         latency_topology: LatencyTopology,
     ) -> Rc<Self> {
+        let mut pool_names: Vec<&String> = pool_listing.keys().collect();
+        pool_names.sort();
+        let pool_index: HashMap<&String, usize> = pool_names
+            .iter()
+            .enumerate()
+            .map(|(idx, name)| (*name, idx))
+            .collect();
+        let pools_count = pool_names.len();
+
+        let max_id = pool_listing.values().flatten().copied().max().unwrap_or(0);
+        let mut pool_of = vec![usize::MAX; max_id + 1];
+        pool_listing.iter().for_each(|(name, members)| {
+            members
+                .iter()
+                .for_each(|id| pool_of[*id] = pool_index[name]);
+        });
+
+        let mut between_pools = vec![None; pools_count * pools_count];
+        latency_topology
+            .between_pools
+            .iter()
+            .for_each(|((from, to), distr)| {
+                between_pools[pool_index[from] * pools_count + pool_index[to]] = Some(*distr);
+            });
+
         Rc::new(Self {
             pool_listing,
-            latency_topology,
+            pool_of,
+            pools_count,
+            between_pools,
+            between_processes: latency_topology.between_processes,
         })
     }
 
     pub(crate) fn get_distribution(&self, from: ProcessId, to: ProcessId) -> Distributions {
-        self.latency_topology
-            .get(&(from, to))
-            .copied()
+        if !self.between_processes.is_empty()
+            && let Some(distr) = self.between_processes.get(&(from, to))
+        {
+            return *distr;
+        }
+
+        self.between_pools[self.pool_of[from] * self.pools_count + self.pool_of[to]]
             .expect("No distr found")
     }
 
     pub(crate) fn list_pool(&self, pool_name: &str) -> &[usize] {
         self.pool_listing.get(pool_name).expect("Invalid pool name")
     }
+
+    // Pool names in index order, see pool_index()
+    pub(crate) fn pool_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.pool_listing.keys().map(String::as_str).collect();
+        names.sort();
+        names
+    }
+
+    pub(crate) fn pools_count(&self) -> usize {
+        self.pools_count
+    }
+
+    pub(crate) fn pool_index(&self, id: ProcessId) -> usize {
+        self.pool_of[id]
+    }
+
+    // All processes in ascending order, as broadcasts reach them
+    pub(crate) fn processes(&self) -> impl Iterator<Item = ProcessId> + '_ {
+        self.pool_of
+            .iter()
+            .enumerate()
+            .filter(|(_, pool)| **pool != usize::MAX)
+            .map(|(id, _)| id)
+    }
 }
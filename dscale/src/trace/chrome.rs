@@ -0,0 +1,135 @@
+// Chrome trace-event format:
+// https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU
+//
+// `ts` and `dur` are in microseconds, converted with the length of a jiffy of the
+// recorded simulation.
+
+use std::io::{self, Write};
+
+use crate::{
+    Jiffies,
+    trace::{Handler, Trace},
+};
+
+fn escape(s: &str) -> String {
+    s.chars()
+        .flat_map(|c| match c {
+            '"' => vec!['\\', '"'],
+            '\\' => vec!['\\', '\\'],
+            c if c.is_control() => format!("\\u{:04x}", c as u32).chars().collect(),
+            c => vec![c],
+        })
+        .collect()
+}
+
+impl Trace {
+    fn micros(&self, time: Jiffies) -> f64 {
+        self.jiffy.as_nanos() as f64 * time.0 as f64 / 1_000.0
+    }
+
+    /// Writes the trace in the Chrome trace-event JSON format.
+    ///
+    /// The output opens in [Perfetto](https://ui.perfetto.dev) or
+    /// `chrome://tracing`:
+    ///
+    /// - every process is a track,
+    /// - every handler invocation is a slice lasting its virtual CPU time,
+    /// - every delivered message is a flow arrow from the sending handler to
+    ///   the receiving one,
+    /// - every fired timer is an instant event.
+    ///
+    /// Timestamps are in simulation time, converted to real time with the
+    /// length of a jiffy declared by
+    /// [`SimulationBuilder::jiffy`](crate::SimulationBuilder::jiffy).
+    /// Arguments such as `sent_at` stay in jiffies.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    ///
+    /// use dscale::Jiffies;
+    /// use dscale::trace::{Handler, Invocation, Trace};
+    ///
+    /// let trace = Trace {
+    ///     invocations: vec![Invocation {
+    ///         process: 1,
+    ///         start: Jiffies(3),
+    ///         cpu: Jiffies(2),
+    ///         handler: Handler::Start,
+    ///     }],
+    ///     jiffy: Duration::from_micros(100),
+    ///     ..Trace::default()
+    /// };
+    ///
+    /// let mut json = Vec::new();
+    /// trace.write_chrome(&mut json).unwrap();
+    /// let json = String::from_utf8(json).unwrap();
+    /// assert!(json.starts_with("{\"traceEvents\":["));
+    /// assert!(json.contains(r#""ts":300,"dur":200"#));
+    /// ```
+    pub fn write_chrome(&self, writer: impl Write) -> io::Result<()> {
+        let mut writer = io::BufWriter::new(writer);
+        let mut events: Vec<String> = Vec::new();
+
+        let mut processes: Vec<_> = self.invocations.iter().map(|i| i.process).collect();
+        processes.sort_unstable();
+        processes.dedup();
+        processes.iter().for_each(|process| {
+            events.push(format!(
+                r#"{{"ph":"M","name":"thread_name","pid":0,"tid":{process},"args":{{"name":"P{process}"}}}}"#
+            ));
+        });
+
+        self.invocations
+            .iter()
+            .enumerate()
+            .for_each(|(flow, invocation)| {
+                let process = invocation.process;
+                let (start, cpu) = (self.micros(invocation.start), self.micros(invocation.cpu));
+                let (name, args) = match &invocation.handler {
+                    Handler::Start => ("start".to_string(), String::new()),
+                    Handler::Message {
+                        from,
+                        name,
+                        size,
+                        sent_at,
+                    } => {
+                        events.push(format!(
+                            r#"{{"ph":"s","name":"{}","cat":"message","id":{flow},"pid":0,"tid":{from},"ts":{}}}"#,
+                            escape(name),
+                            self.micros(*sent_at)
+                        ));
+                        events.push(format!(
+                            r#"{{"ph":"f","bp":"e","name":"{}","cat":"message","id":{flow},"pid":0,"tid":{process},"ts":{start}}}"#,
+                            escape(name)
+                        ));
+                        (
+                            escape(name),
+                            format!(r#","args":{{"from":{from},"size":{size},"sent_at":{}}}"#, sent_at.0),
+                        )
+                    }
+                    Handler::Timer(id) => {
+                        events.push(format!(
+                            r#"{{"ph":"i","s":"t","name":"timer","pid":0,"tid":{process},"ts":{start},"args":{{"id":{id}}}}}"#
+                        ));
+                        ("timer".to_string(), format!(r#","args":{{"id":{id}}}"#))
+                    }
+                    Handler::Storage(id) => ("storage".to_string(), format!(r#","args":{{"id":{id}}}"#)),
+                };
+                events.push(format!(
+                    r#"{{"ph":"X","name":"{name}","pid":0,"tid":{process},"ts":{start},"dur":{cpu}{args}}}"#
+                ));
+            });
+
+        write!(writer, r#"{{"traceEvents":["#)?;
+        for (idx, event) in events.iter().enumerate() {
+            if idx > 0 {
+                writeln!(writer, ",")?;
+            }
+            write!(writer, "{event}")?;
+        }
+        writeln!(writer, r#"],"displayTimeUnit":"ms"}}"#)?;
+        writer.flush()
+    }
+}
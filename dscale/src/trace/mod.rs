@@ -0,0 +1,162 @@
+//! Recording of the simulation timeline.
+//!
+//! When enabled with [`SimulationBuilder::record_trace`], the engine records
+//! every handler invocation: which process ran it, when it started, how much
+//! virtual CPU time it consumed and what triggered it. For delivered messages
+//! the invocation also carries the sender and the send time, so the full
+//! message flow can be reconstructed.
+//!
+//! The recorded [`Trace`] is taken with [`Simulation::take_trace`] and can be
+//! exported to external viewers:
+//!
+//! - [`Trace::write_chrome`]: Chrome trace-event JSON, for Perfetto or `chrome://tracing`
+//! - [`Trace::sequence_chart`]: Mermaid or PlantUML message sequence diagrams
+//!
+//! [`SimulationBuilder::record_trace`]: crate::SimulationBuilder::record_trace
+//! [`Simulation::take_trace`]: crate::Simulation::take_trace
+
+mod chrome;
+mod sequence;
+
+pub use sequence::SequenceChart;
+
+use std::{cell::RefCell, collections::BTreeMap, time::Duration};
+
+use crate::{
+    Jiffies, ProcessId, TimerId, global::cpu, now, storage::OperationId, topology::PoolListing,
+};
+
+/// What triggered a handler invocation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Handler {
+    /// [`ProcessHandle::start`](crate::ProcessHandle::start)
+    Start,
+    /// [`ProcessHandle::on_message`](crate::ProcessHandle::on_message)
+    Message {
+        from: ProcessId,
+        /// [`Message::name`](crate::Message::name) of the delivered message
+        name: &'static str,
+        /// [`Message::virtual_size`](crate::Message::virtual_size) of the delivered message
+        size: usize,
+        /// Time the message left the sender
+        sent_at: Jiffies,
+    },
+    /// [`ProcessHandle::on_timer`](crate::ProcessHandle::on_timer)
+    Timer(TimerId),
+    /// [`ProcessHandle::on_storage`](crate::ProcessHandle::on_storage)
+    Storage(OperationId),
+}
+
+/// A single handler invocation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Invocation {
+    pub process: ProcessId,
+    pub start: Jiffies,
+    /// Virtual CPU time consumed, see [`consume_cpu`](crate::consume_cpu)
+    pub cpu: Jiffies,
+    pub handler: Handler,
+}
+
+/// Handler invocations of a run in execution order.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::fs::File;
+/// use dscale::SimulationBuilder;
+/// # use dscale::{ProcessHandle, ProcessId, MessagePtr, TimerId};
+/// # #[derive(Default)]
+/// # struct MyProcess;
+/// # impl ProcessHandle for MyProcess {
+/// #     fn start(&mut self) {}
+/// #     fn on_message(&mut self, from: ProcessId, message: MessagePtr) {}
+/// #     fn on_timer(&mut self, id: TimerId) {}
+/// # }
+///
+/// let mut simulation = SimulationBuilder::default()
+///     .add_pool::<MyProcess>("nodes", 4)
+///     .record_trace()
+///     .build();
+///
+/// simulation.run();
+///
+/// let trace = simulation.take_trace();
+/// trace
+///     .write_chrome(File::create("trace.json").unwrap())
+///     .unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct Trace {
+    pub invocations: Vec<Invocation>,
+    /// Members of every pool of the simulation
+    pub pools: BTreeMap<String, Vec<ProcessId>>,
+    /// Real-time length of a jiffy of the simulation, see
+    /// [`SimulationBuilder::jiffy`](crate::SimulationBuilder::jiffy)
+    pub jiffy: Duration,
+}
+
+impl Default for Trace {
+    fn default() -> Self {
+        Self {
+            invocations: Vec::new(),
+            pools: BTreeMap::new(),
+            jiffy: Duration::from_millis(1),
+        }
+    }
+}
+
+thread_local! {
+    static RECORDER: RefCell<Option<Trace>> = const { RefCell::new(None) };
+}
+
+pub(crate) fn setup_trace(enabled: bool, pools: &PoolListing, jiffy: Duration) {
+    RECORDER.set(enabled.then(|| {
+        Trace {
+            invocations: Vec::new(),
+            pools: pools
+                .iter()
+                .map(|(name, members)| (name.clone(), members.clone()))
+                .collect(),
+            jiffy,
+        }
+    }));
+}
+
+pub(crate) fn save_trace() -> Option<Trace> {
+    RECORDER.with_borrow(|recorder| recorder.clone())
+}
+
+pub(crate) fn restore_trace(trace: Option<Trace>) {
+    RECORDER.set(trace);
+}
+
+pub(crate) fn drop_trace() {
+    RECORDER.take();
+}
+
+// Must be called right after the handler returns, while its CPU time is known
+pub(crate) fn record(process: ProcessId, handler: Handler) {
+    RECORDER.with_borrow_mut(|recorder| {
+        if let Some(trace) = recorder.as_mut() {
+            trace.invocations.push(Invocation {
+                process,
+                start: now(),
+                cpu: cpu::elapsed(),
+                handler,
+            });
+        }
+    });
+}
+
+pub(crate) fn take_trace() -> Trace {
+    RECORDER.with_borrow_mut(|recorder| {
+        recorder
+            .as_mut()
+            .map(|trace| Trace {
+                invocations: std::mem::take(&mut trace.invocations),
+                pools: trace.pools.clone(),
+                jiffy: trace.jiffy,
+            })
+            .unwrap_or_default()
+    })
+}
@@ -0,0 +1,187 @@
+use std::{collections::BTreeSet, ops::Range};
+
+use crate::{
+    Jiffies, ProcessId,
+    trace::{Handler, Trace},
+};
+
+// Strips module paths, keeping generic arguments: a::B<c::D> -> B<D>
+fn short_name(name: &str) -> String {
+    let mut short = String::new();
+    let mut segment_start = 0;
+    let mut chars = name.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == ':' && chars.peek() == Some(&':') {
+            chars.next();
+            short.truncate(segment_start);
+        } else {
+            short.push(c);
+            if !(c.is_alphanumeric() || c == '_') {
+                segment_start = short.len();
+            }
+        }
+    }
+    short
+}
+
+struct Arrow {
+    from: ProcessId,
+    to: ProcessId,
+    label: String,
+}
+
+/// Message sequence diagram of a recorded [`Trace`].
+///
+/// Created with [`Trace::sequence_chart`]. Every delivered message becomes an
+/// arrow labelled with the message name, its send time and its arrival time.
+/// Timers and storage completions are not shown.
+///
+/// # Examples
+///
+/// ```rust
+/// use dscale::Jiffies;
+/// use dscale::trace::Trace;
+///
+/// let trace = Trace::default(); // Usually taken from a finished simulation
+/// let mermaid = trace
+///     .sequence_chart()
+///     .window(Jiffies(0)..Jiffies(500))
+///     .to_mermaid();
+///
+/// assert_eq!(mermaid, "sequenceDiagram\n");
+/// ```
+pub struct SequenceChart<'a> {
+    trace: &'a Trace,
+    window: Range<Jiffies>,
+    pools: Vec<String>,
+}
+
+impl Trace {
+    /// Starts a message sequence diagram of this trace.
+    ///
+    /// Meant for runs with a handful of processes; see [`SequenceChart`].
+    pub fn sequence_chart(&self) -> SequenceChart<'_> {
+        SequenceChart {
+            trace: self,
+            window: Jiffies(0)..Jiffies(usize::MAX),
+            pools: Vec::new(),
+        }
+    }
+}
+
+impl SequenceChart<'_> {
+    /// Keeps only messages delivered within the window.
+    pub fn window(mut self, window: Range<Jiffies>) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Keeps only messages between processes of the given pools.
+    ///
+    /// Can be called several times to select multiple pools. All pools are
+    /// shown by default.
+    pub fn pool(mut self, name: &str) -> Self {
+        self.pools.push(name.to_string());
+        self
+    }
+
+    /// Renders the diagram in Mermaid syntax.
+    pub fn to_mermaid(&self) -> String {
+        self.render(
+            "sequenceDiagram\n",
+            "",
+            |process, pool| format!("    participant P{process} as P{process} ({pool})\n"),
+            |arrow| format!("    P{}->>P{}: {}\n", arrow.from, arrow.to, arrow.label),
+        )
+    }
+
+    /// Renders the diagram in PlantUML syntax.
+    pub fn to_plantuml(&self) -> String {
+        self.render(
+            "@startuml\n",
+            "@enduml\n",
+            |process, pool| format!("participant \"P{process} ({pool})\" as P{process}\n"),
+            |arrow| format!("P{} -> P{} : {}\n", arrow.from, arrow.to, arrow.label),
+        )
+    }
+}
+
+impl SequenceChart<'_> {
+    fn selected(&self) -> Option<BTreeSet<ProcessId>> {
+        if self.pools.is_empty() {
+            return None;
+        }
+        Some(
+            self.pools
+                .iter()
+                .flat_map(|name| {
+                    self.trace
+                        .pools
+                        .get(name)
+                        .expect("No pool found")
+                        .iter()
+                        .copied()
+                })
+                .collect(),
+        )
+    }
+
+    fn arrows(&self) -> Vec<Arrow> {
+        let selected = self.selected();
+        let shown = |process: ProcessId| selected.as_ref().is_none_or(|s| s.contains(&process));
+
+        self.trace
+            .invocations
+            .iter()
+            .filter(|invocation| self.window.contains(&invocation.start))
+            .filter_map(|invocation| match &invocation.handler {
+                Handler::Message {
+                    from,
+                    name,
+                    sent_at,
+                    ..
+                } if shown(*from) && shown(invocation.process) => Some(Arrow {
+                    from: *from,
+                    to: invocation.process,
+                    label: format!(
+                        "{} [{} → {}]",
+                        short_name(name),
+                        sent_at.0,
+                        invocation.start.0
+                    ),
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn pool_of(&self, process: ProcessId) -> &str {
+        self.trace
+            .pools
+            .iter()
+            .find(|(_, members)| members.contains(&process))
+            .map_or("?", |(name, _)| name.as_str())
+    }
+
+    fn render(
+        &self,
+        header: &str,
+        footer: &str,
+        participant: impl Fn(ProcessId, &str) -> String,
+        arrow: impl Fn(&Arrow) -> String,
+    ) -> String {
+        let arrows = self.arrows();
+        let participants: BTreeSet<ProcessId> = arrows
+            .iter()
+            .flat_map(|arrow| [arrow.from, arrow.to])
+            .collect();
+
+        let mut chart = header.to_string();
+        participants
+            .iter()
+            .for_each(|process| chart.push_str(&participant(*process, self.pool_of(*process))));
+        arrows.iter().for_each(|a| chart.push_str(&arrow(a)));
+        chart.push_str(footer);
+        chart
+    }
+}
@@ -1,4 +1,4 @@
-use std::{fs::File, sync::Mutex};
+use std::{fs::File, sync::Mutex, time::Duration};
 
 use dag_based::bullshark::Bullshark;
 use dscale::{
@@ -11,6 +11,7 @@ use std::io::Write;
 fn main() {
     let k_validators = 1000;
     let mb_per_sec = [8000, 9000, 10000, 11000];
+    let bls = false; // Aggregate signatures with BLS12-381 instead of Ed25519
 
     mb_per_sec.into_iter().for_each(|bandwidth| {
         let file = Mutex::new(File::create(format!("bullshark_{}.csv", bandwidth)).unwrap());
@@ -19,6 +20,7 @@ fn main() {
 
         seeds.into_par_iter().for_each(|seed| {
             anykv::set::<(f64, usize)>("avg_latency", (0.0, 0));
+            anykv::set::<bool>("bls", bls);
 
             let mut sim = SimulationBuilder::default()
                 .add_pool::<Bullshark>("Validators", k_validators)
@@ -26,7 +28,8 @@ fn main() {
                     "Validators",
                     Distributions::Normal(Jiffies(50), Jiffies(10)),
                 )])
-                .time_budget(Jiffies(60_000)) // Simulating 1 min of real time execution
+                .jiffy(Duration::from_millis(1))
+                .time_budget(Duration::from_secs(60))
                 .nic_bandwidth(BandwidthDescription::Bounded(
                     bandwidth * 1024 * 1024 / (8 * 1000), // bandwidth Mb/sec NICs
                 ))
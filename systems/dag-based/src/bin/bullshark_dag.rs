@@ -0,0 +1,62 @@
+// Dumps the local DAG of a single validator as Graphviz DOT:
+// cargo run --bin bullshark_dag && dot -Tsvg bullshark_dag.dot -o bullshark_dag.svg
+
+use std::fs;
+
+use dag_based::bullshark::Bullshark;
+use dscale::{
+    Distributions, LatencyDescription, MessagePtr, ProcessHandle, ProcessId, SimulationBuilder,
+    TimerId, global::anykv, rank, schedule_timer_after, time::Jiffies,
+};
+
+const DUMPED_VALIDATOR: ProcessId = 1;
+const DUMP_AT: Jiffies = Jiffies(60_000);
+const ROUNDS: std::ops::Range<usize> = 0..12;
+
+// Bullshark ignores foreign timers, so the dump timer can be scheduled alongside its own
+#[derive(Default)]
+struct DumpingBullshark {
+    inner: Bullshark,
+    dump_timer: Option<TimerId>,
+}
+
+impl ProcessHandle for DumpingBullshark {
+    fn start(&mut self) {
+        if rank() == DUMPED_VALIDATOR {
+            self.inner.export_dag(ROUNDS);
+            self.dump_timer = Some(schedule_timer_after(DUMP_AT));
+        }
+        self.inner.start();
+    }
+
+    fn on_message(&mut self, from: ProcessId, message: MessagePtr) {
+        self.inner.on_message(from, message);
+    }
+
+    fn on_timer(&mut self, id: TimerId) {
+        if Some(id) == self.dump_timer {
+            anykv::set::<String>("dag_dot", self.inner.dag_to_dot());
+        } else {
+            self.inner.on_timer(id);
+        }
+    }
+}
+
+fn main() {
+    let mut sim = SimulationBuilder::default()
+        .add_pool::<DumpingBullshark>("Validators", 4)
+        .latency_topology(&[LatencyDescription::WithinPool(
+            "Validators",
+            Distributions::Normal(Jiffies(50), Jiffies(10)),
+        )])
+        .time_budget(DUMP_AT + Jiffies(1))
+        .seed(42)
+        .build();
+
+    anykv::set::<(f64, usize)>("avg_latency", (0.0, 0));
+    anykv::set::<String>("dag_dot", String::new());
+
+    sim.run();
+
+    fs::write("bullshark_dag.dot", anykv::get::<String>("dag_dot")).unwrap();
+}
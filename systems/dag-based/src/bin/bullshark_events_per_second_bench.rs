@@ -0,0 +1,36 @@
+// Events per second of the engine running Bullshark:
+// cargo run --release --bin bullshark_events_per_second_bench
+
+use std::time::Instant;
+
+use dag_based::bullshark::Bullshark;
+use dscale::{
+    BandwidthDescription, Distributions, LatencyDescription, SimulationBuilder, global::anykv,
+    time::Jiffies,
+};
+
+fn main() {
+    anykv::set::<(f64, usize)>("avg_latency", (0.0, 0));
+
+    let mut sim = SimulationBuilder::default()
+        .add_pool::<Bullshark>("Validators", 200)
+        .latency_topology(&[LatencyDescription::WithinPool(
+            "Validators",
+            Distributions::Normal(Jiffies(50), Jiffies(10)),
+        )])
+        .time_budget(Jiffies(3_000))
+        .nic_bandwidth(BandwidthDescription::Gbps(10))
+        .seed(4567898765)
+        .build();
+
+    let start = Instant::now();
+    sim.run();
+    let elapsed = start.elapsed();
+
+    println!(
+        "bullshark {:>10} events in {:>8.2?}: {:>10.0} events/s",
+        sim.executed_events(),
+        elapsed,
+        sim.executed_events() as f64 / elapsed.as_secs_f64()
+    );
+}
@@ -1,4 +1,4 @@
-use std::{fs::File, sync::Mutex};
+use std::{fs::File, sync::Mutex, time::Duration};
 
 use dag_based::sparse_bullshark::SparseBullshark;
 use dscale::{
@@ -35,7 +35,8 @@ fn main() {
                     "Validators",
                     Distributions::Normal(Jiffies(50), Jiffies(10)),
                 )])
-                .time_budget(Jiffies(36000_000)) // Simulating 10 hours of real time execution
+                .jiffy(Duration::from_millis(1))
+                .time_budget(Duration::from_secs(10 * 60 * 60))
                 .nic_bandwidth(BandwidthDescription::Bounded(5 * 1024 * 1024 / (8 * 1000)))
                 .seed(*seed)
                 .build();
@@ -3,6 +3,7 @@
 
 use std::{
     collections::BTreeSet,
+    ops::Range,
     rc::{Rc, Weak},
 };
 
@@ -22,6 +23,10 @@ pub struct Bullshark {
     buffer: BTreeSet<VertexPtr>,
     last_ordered_round: usize,
     ordered_anchors_stack: Vec<VertexPtr>,
+    // Rounds exported as DOT and the committed anchors bounding paths through them,
+    // kept only once export is enabled
+    exported_rounds: Option<Range<usize>>,
+    committed_anchors: Vec<VertexPtr>,
     wait: bool,
     current_timer: TimerId,
 }
@@ -37,6 +42,8 @@ impl Default for Bullshark {
             buffer: BTreeSet::new(),
             last_ordered_round: 0,
             ordered_anchors_stack: Vec::new(),
+            exported_rounds: None,
+            committed_anchors: Vec::new(),
             wait: true,
             current_timer: 0,
         }
@@ -71,14 +78,13 @@ impl ProcessHandle for Bullshark {
                     debug_assert!(v.round == 0);
                     self.dag.add_vertex(v.clone());
                     self.try_advance_round();
-                    return;
                 }
 
                 VertexMessage::Vertex(v) => {
                     debug_process!("Got vertex from: {from}");
 
                     // Validity check
-                    if self.bad_vertex(&v, from) {
+                    if self.bad_vertex(v, from) {
                         return;
                     }
 
@@ -118,7 +124,7 @@ impl ProcessHandle for Bullshark {
                                 if self.dag[self.round]
                                     .iter()
                                     .flatten()
-                                    .map(|v| {
+                                    .filter(|v| {
                                         v.strong_edges
                                             .iter()
                                             .map(|weak| weak.upgrade().unwrap())
@@ -152,6 +158,41 @@ impl ProcessHandle for Bullshark {
     }
 }
 
+impl Bullshark {
+    /// Enables [`dag_to_dot`](Self::dag_to_dot) for the given rounds.
+    ///
+    /// Committed anchors are only remembered from then on, and only those
+    /// within the rounds plus the closest one on each side, so call it
+    /// before the validator starts.
+    pub fn export_dag(&mut self, rounds: Range<usize>) {
+        self.exported_rounds = Some(rounds);
+        self.committed_anchors.clear();
+    }
+
+    /// Graphviz DOT of the local DAG of this validator within the rounds
+    /// given to [`export_dag`](Self::export_dag).
+    ///
+    /// Vertices are grouped by round, ordered ones are filled green, anchors
+    /// are drawn bold and committed anchors together with the strong paths
+    /// between consecutive ones are drawn red. Render with `dot -Tsvg`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if export was not enabled with [`export_dag`](Self::export_dag).
+    pub fn dag_to_dot(&self) -> String {
+        let rounds = self
+            .exported_rounds
+            .clone()
+            .expect("DAG export is not enabled");
+        self.dag.to_dot(
+            rounds,
+            // Note: anchor vertices are on even rounds
+            |round| (round % 2 == 0).then(|| self.get_leader_id(round)),
+            &self.committed_anchors,
+        )
+    }
+}
+
 // Utils
 impl Bullshark {
     fn adversary_threshold(&self) -> usize {
@@ -182,8 +223,7 @@ impl Bullshark {
             strong_edges: self.dag[round - 1]
                 .iter()
                 .flatten() // Remove option
-                .cloned()
-                .map(|strong| Rc::downgrade(&strong))
+                .map(Rc::downgrade)
                 .collect::<Vec<Weak<Vertex>>>(),
             creation_time: now(),
         })
@@ -194,7 +234,7 @@ impl Bullshark {
     }
 
     fn get_leader_id(&self, round: usize) -> ProcessId {
-        return round % self.proc_num + 1;
+        round % self.proc_num + 1
     }
 
     fn get_anchor(&self, round: usize) -> Option<VertexPtr> {
@@ -258,7 +298,7 @@ impl Bullshark {
         if v.source == self.get_leader_id(v.round) {
             self.try_ordering(v);
         }
-        return true;
+        true
     }
 }
 
@@ -273,7 +313,7 @@ impl Bullshark {
         let maybe_anchor = self.get_anchor(v.round - 2);
 
         match maybe_anchor {
-            None => return,
+            None => (),
             Some(anchor) => {
                 let vote_count = v
                     .strong_edges
@@ -300,15 +340,20 @@ impl Bullshark {
             let maybe_prev_anchor = self.get_anchor(r);
             match maybe_prev_anchor {
                 None => {
-                    r = r - 2; // Skip anchor and proceed to the next
+                    r -= 2; // Skip anchor and proceed to the next
                     continue;
                 }
                 Some(prev_anchor) => {
                     if self.dag.path_exists(&anchor, &prev_anchor) {
                         self.ordered_anchors_stack.push(prev_anchor.clone());
                         anchor = prev_anchor;
+                    } else {
+                        debug_process!(
+                            "Skipping anchor of round {r}: no path from anchor of round {}",
+                            anchor.round
+                        );
                     }
-                    r = r - 2;
+                    r -= 2;
                 }
             }
         }
@@ -320,6 +365,25 @@ impl Bullshark {
     fn order_history(&mut self) {
         while let Some(anchor) = self.ordered_anchors_stack.pop() {
             self.dag.order_from(&anchor);
+            self.record_committed_anchor(anchor);
+        }
+    }
+
+    // Anchors are committed in increasing rounds
+    fn record_committed_anchor(&mut self, anchor: VertexPtr) {
+        let Some(rounds) = &self.exported_rounds else {
+            return;
+        };
+        if anchor.round < rounds.start {
+            // Only the latest one before the range bounds a path into it
+            self.committed_anchors.clear();
+            self.committed_anchors.push(anchor);
+        } else if self
+            .committed_anchors
+            .last()
+            .is_none_or(|last| last.round < rounds.end)
+        {
+            self.committed_anchors.push(anchor);
         }
     }
 }
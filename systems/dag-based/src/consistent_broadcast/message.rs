@@ -1,6 +1,13 @@
 use std::rc::Rc;
 
-use dscale::{Message, ProcessId};
+use dscale::{
+    Message, ProcessId,
+    global::anykv,
+    helpers::{
+        Threshold,
+        crypto::{self, HASH_SIZE, SignatureScheme},
+    },
+};
 
 #[derive(Clone, PartialEq, Eq, Hash, Copy)]
 pub struct BCBMessageId {
@@ -14,15 +21,44 @@ pub enum BCBMessage {
     Certificate(usize, BCBMessageId),
 }
 
-pub const ID_SIZE: usize = 128;
-pub const SIG_SIZE: usize = 64; // For example Ed25519 or Secp256k1
+// Ed25519 by default, with certificates modeled by the bitmap of their signers.
+// Set the "bls" parameter in anykv to aggregate signatures with BLS12-381 instead
+fn bls() -> bool {
+    anykv::try_get::<bool>("bls").unwrap_or(false)
+}
+
+fn signatures() -> SignatureScheme {
+    if bls() {
+        crypto::BLS12_381
+    } else {
+        crypto::ED25519
+    }
+}
+
+// Digest of the message, signed by its sender, together with the sender's key
+pub fn id_size() -> usize {
+    let signatures = signatures();
+    HASH_SIZE + signatures.signature_size + signatures.public_key_size
+}
+
+// Certificate of a quorum out of `validators`
+pub fn certificate_size(validators: usize) -> usize {
+    if bls() {
+        crypto::BLS12_381.certificate_size(
+            Threshold::TwoFPlusOne.weight(validators as u64) as usize,
+            validators,
+        )
+    } else {
+        validators / 8
+    }
+}
 
 impl Message for BCBMessage {
     fn virtual_size(&self) -> usize {
         match self {
-            BCBMessage::Initiate((_, m)) => ID_SIZE + m.virtual_size(),
-            BCBMessage::Signature(_) => SIG_SIZE,
-            BCBMessage::Certificate(k_validators, _) => ID_SIZE + (k_validators / 8),
+            BCBMessage::Initiate((_, m)) => id_size() + m.virtual_size(),
+            BCBMessage::Signature(_) => signatures().signature_size,
+            BCBMessage::Certificate(k_validators, _) => id_size() + certificate_size(*k_validators),
         }
     }
 }
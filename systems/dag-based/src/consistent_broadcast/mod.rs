@@ -1,13 +1,18 @@
 mod message;
 pub(crate) use message::BCBMessage;
-pub(crate) use message::ID_SIZE;
+pub(crate) use message::certificate_size;
+pub(crate) use message::id_size;
 
 use std::{
     collections::{HashMap, HashSet},
     rc::Rc,
 };
 
-use dscale::{Message, MessagePtr, ProcessId, broadcast, rank, send_to};
+use dscale::{
+    Message, MessagePtr, ProcessId, broadcast,
+    helpers::{QuorumTracker, Threshold},
+    rank, send_to,
+};
 
 use crate::consistent_broadcast::message::BCBMessageId;
 
@@ -15,7 +20,7 @@ use crate::consistent_broadcast::message::BCBMessageId;
 // Algorithm 3.17: Signed Echo Broadcast
 #[derive(Default)]
 pub struct ByzantineConsistentBroadcast {
-    messages: HashMap<BCBMessageId, (Rc<dyn Message>, usize)>, // usize -> signature count, once it reaches 2f+1 message pops out
+    messages: HashMap<BCBMessageId, (Rc<dyn Message>, QuorumTracker)>, // Signers, once they reach 2f+1 message pops out
     waiting_certificates: HashSet<BCBMessageId>,
     process_id: ProcessId,
     message_id: usize,
@@ -23,12 +28,8 @@ pub struct ByzantineConsistentBroadcast {
 }
 
 impl ByzantineConsistentBroadcast {
-    fn adversary_threshold(&self) -> usize {
-        (self.proc_num - 1) / 3
-    }
-
-    fn quorum_size(&self) -> usize {
-        2 * self.adversary_threshold() + 1
+    fn signatures(&self) -> QuorumTracker {
+        QuorumTracker::new(self.proc_num, &[Threshold::TwoFPlusOne])
     }
 
     fn next_unique_message_id(&mut self) -> BCBMessageId {
@@ -44,7 +45,8 @@ impl ByzantineConsistentBroadcast {
     pub(crate) fn reliably_broadcast(&mut self, message: impl Message + 'static) {
         let next_id = self.next_unique_message_id();
         let shared = Rc::new(message);
-        self.messages.insert(next_id, (shared.clone(), 0));
+        self.messages
+            .insert(next_id, (shared.clone(), self.signatures()));
         broadcast(BCBMessage::Initiate((next_id, shared)));
     }
 
@@ -60,38 +62,37 @@ impl ByzantineConsistentBroadcast {
     ) -> Option<MessagePtr> {
         match message.as_ref() {
             BCBMessage::Certificate(_, id) => {
-                match self.messages.remove(&id) {
+                match self.messages.remove(id) {
                     // Due to network latency we got certificate gathered by some other quorum (not including us)
                     None => {
                         self.waiting_certificates.insert(*id);
-                        return None;
+                        None
                     }
-                    Some((message, _)) => return Some(MessagePtr(message)),
+                    Some((message, _)) => Some(MessagePtr(message)),
                 }
             }
             BCBMessage::Initiate((id, m)) => {
                 if id.process_id != self.process_id {
-                    if self.waiting_certificates.contains(&id) {
-                        self.waiting_certificates.remove(&id);
+                    if self.waiting_certificates.contains(id) {
+                        self.waiting_certificates.remove(id);
                         return Some(MessagePtr(m.clone()));
                     }
-                    self.messages.insert(*id, (m.clone(), 0));
+                    self.messages.insert(*id, (m.clone(), self.signatures()));
                 }
                 send_to(from, BCBMessage::Signature(*id));
-                return None;
+                None
             }
             BCBMessage::Signature(id) => {
-                match self.messages.get_mut(&id) {
+                match self.messages.get_mut(id) {
                     None => {
                         // Message already gathered quorum and was poped.
-                        return None;
+                        None
                     }
-                    Some(message_state) => {
-                        message_state.1 += 1;
-                        if message_state.1 == self.quorum_size() {
+                    Some((_, signatures)) => {
+                        if !signatures.vote(from, ()).is_empty() {
                             broadcast(BCBMessage::Certificate(self.proc_num, *id));
                         }
-                        return None;
+                        None
                     }
                 }
             }
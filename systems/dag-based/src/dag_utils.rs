@@ -1,6 +1,7 @@
 use std::{
-    collections::VecDeque,
-    ops::Index,
+    collections::{BTreeSet, VecDeque},
+    fmt::Write,
+    ops::{Index, Range},
     rc::{Rc, Weak},
 };
 
@@ -11,7 +12,7 @@ use dscale::{
     time::{self},
 };
 
-use crate::consistent_broadcast::ID_SIZE;
+use crate::consistent_broadcast::{self, id_size};
 
 const GC_REMAIN: usize = usize::MAX;
 
@@ -39,22 +40,13 @@ impl PartialEq for Vertex {
     fn eq(&self, other: &Self) -> bool {
         (self.round, self.source).eq(&(other.round, other.source))
     }
-    fn ne(&self, other: &Self) -> bool {
-        (self.round, self.source).ne(&(other.round, other.source))
-    }
 }
 
 impl Eq for Vertex {}
 
 impl PartialOrd for Vertex {
-    fn ge(&self, other: &Self) -> bool {
-        (self.round, self.source).ge(&(other.round, other.source))
-    }
-    fn le(&self, other: &Self) -> bool {
-        (self.round, self.source).le(&(other.round, other.source))
-    }
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        (self.round, self.source).partial_cmp(&(other.round, other.source))
+        Some(self.cmp(other))
     }
 }
 
@@ -65,7 +57,7 @@ impl Ord for Vertex {
 }
 
 fn certificate_size() -> usize {
-    (process_number() / 8) + ID_SIZE
+    consistent_broadcast::certificate_size(process_number()) + id_size()
 }
 
 #[derive(Clone)]
@@ -106,7 +98,7 @@ impl RoundBasedDAG {
         let mut queue = VecDeque::new();
         queue.push_back(v.clone());
 
-        while queue.len() > 0 {
+        while !queue.is_empty() {
             let curr = queue.pop_front().unwrap();
 
             let strong_edges: Vec<VertexPtr> = curr
@@ -125,7 +117,7 @@ impl RoundBasedDAG {
                         anykv::modify::<(f64, usize)>(
                             "avg_latency",
                             |(prev_avg_latency, prev_total_ordered)| {
-                                let vertex_latency = now() - edge.creation_time;
+                                let vertex_latency = now().saturating_sub(edge.creation_time);
                                 *prev_avg_latency = (vertex_latency.0 as f64
                                     + (*prev_avg_latency * *prev_total_ordered as f64))
                                     as f64
@@ -145,7 +137,7 @@ impl RoundBasedDAG {
     // v & u should be already in the DAG
     // u.round <= v.round
     pub fn path_exists(&mut self, v: &VertexPtr, u: &VertexPtr) -> bool {
-        if same_vertex(&v, &u) {
+        if same_vertex(v, u) {
             return true;
         }
 
@@ -157,7 +149,7 @@ impl RoundBasedDAG {
         let mut queue = VecDeque::new();
         queue.push_back(v.clone());
 
-        while queue.len() > 0 {
+        while !queue.is_empty() {
             let curr = queue.pop_front().unwrap();
 
             let strong_edges: Vec<VertexPtr> = curr
@@ -171,7 +163,7 @@ impl RoundBasedDAG {
                 if edge.round < u.round {
                     continue;
                 }
-                if same_vertex(&edge, &u) {
+                if same_vertex(&edge, u) {
                     return true;
                 } else {
                     let read_round = self.round(edge.round);
@@ -185,7 +177,7 @@ impl RoundBasedDAG {
             }
         }
 
-        return false;
+        false
     }
 
     pub fn add_vertex(&mut self, v: VertexPtr) {
@@ -205,9 +197,129 @@ impl RoundBasedDAG {
     pub fn current_max_allocated_round(&self) -> usize {
         self.current_allocated_rounds().saturating_sub(1)
     }
+
+    // Graphviz DOT of the local DAG within the round range (clamped to what is allocated).
+    // Vertices are grouped by round and filled if ordered, anchors (as chosen by leader_of)
+    // are drawn bold, committed anchors and the strong paths between consecutive ones in red.
+    pub fn to_dot(
+        &self,
+        rounds: Range<usize>,
+        leader_of: impl Fn(usize) -> Option<ProcessId>,
+        committed_anchors: &[VertexPtr],
+    ) -> String {
+        let rounds =
+            rounds.start.max(self.gc_offset)..rounds.end.min(self.current_allocated_rounds());
+        let committed: BTreeSet<(usize, ProcessId)> = committed_anchors
+            .iter()
+            .map(|anchor| (anchor.round, anchor.source))
+            .collect();
+        let commit_path = self.commit_path(committed_anchors);
+
+        let mut dot = String::from("digraph DAG {\n    rankdir=LR;\n    edge [dir=back];\n");
+
+        rounds.clone().for_each(|round| {
+            let _ = writeln!(
+                dot,
+                "    subgraph cluster_r{round} {{\n        label=\"round {round}\";"
+            );
+            self[round].iter().flatten().for_each(|v| {
+                let mut attributes = vec![format!("label=\"P{}\"", v.source)];
+                if self.ordered[self.round(round)][v.source] {
+                    attributes.push("style=filled, fillcolor=palegreen".to_string());
+                }
+                if committed.contains(&(v.round, v.source)) {
+                    attributes.push("color=red, penwidth=3".to_string());
+                } else if leader_of(round) == Some(v.source) {
+                    attributes.push("penwidth=3".to_string());
+                }
+                let _ = writeln!(
+                    dot,
+                    "        v{}_{} [{}];",
+                    v.round,
+                    v.source,
+                    attributes.join(", ")
+                );
+            });
+            dot.push_str("    }\n");
+        });
+
+        rounds.clone().for_each(|round| {
+            self[round].iter().flatten().for_each(|v| {
+                v.strong_edges
+                    .iter()
+                    .filter_map(Weak::upgrade)
+                    .filter(|edge| rounds.contains(&edge.round))
+                    .for_each(|edge| {
+                        let on_path = commit_path.contains(&(v.round, v.source))
+                            && commit_path.contains(&(edge.round, edge.source));
+                        let style = if on_path {
+                            " [color=red, penwidth=2]"
+                        } else {
+                            ""
+                        };
+                        let _ = writeln!(
+                            dot,
+                            "    v{}_{} -> v{}_{}{};",
+                            edge.round, edge.source, v.round, v.source, style
+                        );
+                    });
+            });
+        });
+
+        dot.push_str("}\n");
+        dot
+    }
 }
 
 impl RoundBasedDAG {
+    // Vertices lying on some strong path between consecutive committed anchors
+    fn commit_path(&self, committed_anchors: &[VertexPtr]) -> BTreeSet<(usize, ProcessId)> {
+        let mut path = BTreeSet::new();
+        committed_anchors.windows(2).for_each(|pair| {
+            let (low, high) = (&pair[0], &pair[1]);
+
+            // Everything reachable from the later anchor down to the earlier one's round
+            let mut reachable = vec![high.clone()];
+            let mut seen = BTreeSet::from([(high.round, high.source)]);
+            let mut idx = 0;
+            while idx < reachable.len() {
+                let curr = reachable[idx].clone();
+                idx += 1;
+                // Edges below the GC horizon are gone and cannot lie on a path
+                curr.strong_edges
+                    .iter()
+                    .filter_map(Weak::upgrade)
+                    .filter(|edge| edge.round >= low.round)
+                    .for_each(|edge| {
+                        if seen.insert((edge.round, edge.source)) {
+                            reachable.push(edge);
+                        }
+                    });
+            }
+
+            // Of those, keep the ones that reach the earlier anchor (rounds ascending)
+            reachable.sort();
+            let mut reaches = BTreeSet::from([(low.round, low.source)]);
+            reachable
+                .iter()
+                .filter(|v| v.round > low.round)
+                .for_each(|v| {
+                    let hit = v
+                        .strong_edges
+                        .iter()
+                        .filter_map(Weak::upgrade)
+                        .any(|edge| reaches.contains(&(edge.round, edge.source)));
+                    if hit {
+                        reaches.insert((v.round, v.source));
+                    }
+                });
+            if reaches.contains(&(high.round, high.source)) {
+                path.extend(reaches);
+            }
+        });
+        path
+    }
+
     // Round with GC offset assuming base > offset
     fn round(&self, base: usize) -> usize {
         base - self.gc_offset
@@ -55,11 +55,10 @@ impl ProcessHandle for DAGRider {
                 VertexMessage::Genesis(v) => {
                     debug_assert!(v.round == 0);
                     self.dag.add_vertex(v.clone());
-                    return;
                 }
 
                 VertexMessage::Vertex(v) => {
-                    if self.bad_vertex(&v, from) {
+                    if self.bad_vertex(v, from) {
                         return;
                     }
                     self.buffer.insert(v.clone());
@@ -78,9 +77,8 @@ impl DAGRider {
         let ready_to_be_added = self
             .buffer
             .iter()
-            .cloned()
-            .filter(|v| v.round <= self.round)
-            .filter(|v| {
+            .filter(|&v| v.round <= self.round)
+            .filter(|&v| {
                 v.strong_edges
                     .iter()
                     .map(|weak| weak.upgrade().unwrap())
@@ -89,6 +87,7 @@ impl DAGRider {
                         Some(ref vertex) => same_vertex(&parent, vertex),
                     })
             })
+            .cloned()
             .collect::<Vec<VertexPtr>>();
 
         self.buffer.retain(|v| !ready_to_be_added.contains(v));
@@ -103,7 +102,7 @@ impl DAGRider {
 
     fn try_advance_round(&mut self) {
         if self.quorum_reached_for_round(self.round) {
-            if self.round % 4 == 0 && self.round != 0 {
+            if self.round.is_multiple_of(4) && self.round != 0 {
                 self.wave_ready(self.round / 4);
             }
             self.round += 1;
@@ -139,8 +138,7 @@ impl DAGRider {
             strong_edges: self.dag[round - 1]
                 .iter()
                 .flatten()
-                .cloned()
-                .map(|strong| Rc::downgrade(&strong))
+                .map(Rc::downgrade)
                 .collect::<Vec<Weak<Vertex>>>(),
             creation_time: now(),
         })
@@ -151,7 +149,7 @@ impl DAGRider {
     }
 
     fn get_leader_id(&self, round: usize) -> ProcessId {
-        return round % self.proc_num + 1;
+        round % self.proc_num + 1
     }
 
     fn round(&self, w: usize, k: usize) -> usize {
@@ -161,7 +159,7 @@ impl DAGRider {
     fn get_wave_vertex_leader(&self, w: usize) -> Option<VertexPtr> {
         let round = self.round(w, 1);
         let leader = self.get_leader_id(round);
-        return self.dag[round][leader].clone();
+        self.dag[round][leader].clone()
     }
 }
 
@@ -175,13 +173,12 @@ impl DAGRider {
 
         let non_none_vertices = self.dag[self.round(w, 4)]
             .iter()
-            .filter(|v| v.is_some())
-            .map(|v| v.clone().unwrap())
+            .filter_map(|v| v.clone())
             .collect::<Vec<VertexPtr>>();
 
         if non_none_vertices
             .into_iter()
-            .filter(|v| self.dag.path_exists(&v, &leader))
+            .filter(|v| self.dag.path_exists(v, &leader))
             .count()
             < self.quorum_size()
         {
@@ -191,10 +188,11 @@ impl DAGRider {
         self.leaders_stack.push(leader.clone());
 
         for w_ in ((self.decided_wave + 1)..=(w - 1)).rev() {
-            let v_ = self.get_wave_vertex_leader(w_);
-            if v_.is_some() && self.dag.path_exists(&leader, v_.as_ref().unwrap()) {
-                self.leaders_stack.push(v_.clone().unwrap());
-                leader = v_.unwrap();
+            if let Some(v_) = self.get_wave_vertex_leader(w_)
+                && self.dag.path_exists(&leader, &v_)
+            {
+                self.leaders_stack.push(v_.clone());
+                leader = v_;
             }
         }
         self.decided_wave = w;
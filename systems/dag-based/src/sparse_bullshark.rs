@@ -76,7 +76,6 @@ impl ProcessHandle for SparseBullshark {
                     debug_assert!(v.round == 0);
                     self.dag.add_vertex(v.clone());
                     self.try_advance_round();
-                    return;
                 }
 
                 VertexMessage::Vertex(v) => {
@@ -121,7 +120,7 @@ impl ProcessHandle for SparseBullshark {
                                 if self.dag[self.round]
                                     .iter()
                                     .flatten()
-                                    .map(|v| {
+                                    .filter(|v| {
                                         v.strong_edges
                                             .iter()
                                             .map(|weak| weak.upgrade().unwrap())
@@ -237,7 +236,7 @@ impl SparseBullshark {
     }
 
     fn get_leader_id(&self, round: usize) -> ProcessId {
-        return round % self.proc_num + 1;
+        round % self.proc_num + 1
     }
 
     fn get_anchor(&self, round: usize) -> Option<VertexPtr> {
@@ -299,7 +298,7 @@ impl SparseBullshark {
         if v.source == self.get_leader_id(v.round) {
             self.try_ordering(v);
         }
-        return true;
+        true
     }
 }
 
@@ -314,7 +313,7 @@ impl SparseBullshark {
         let maybe_anchor = self.get_anchor(v.round - 2);
 
         match maybe_anchor {
-            None => return,
+            None => (),
             Some(anchor) => {
                 let vote_count = self.dag[v.round - 1]
                     .iter()
@@ -341,7 +340,7 @@ impl SparseBullshark {
             let maybe_prev_anchor = self.get_anchor(r);
             match maybe_prev_anchor {
                 None => {
-                    r = r - 2; // Skip anchor and proceed to the next
+                    r -= 2; // Skip anchor and proceed to the next
                     continue;
                 }
                 Some(prev_anchor) => {
@@ -349,7 +348,7 @@ impl SparseBullshark {
                         self.ordered_anchors_stack.push(prev_anchor.clone());
                         anchor = prev_anchor;
                     }
-                    r = r - 2;
+                    r -= 2;
                 }
             }
         }
@@ -0,0 +1,38 @@
+// Events per second of the engine on a bandwidth-bound run:
+// cargo run --release --bin events_per_second_bench
+//
+// The Bullshark counterpart lives in dag-based (bullshark_events_per_second_bench).
+
+use std::time::Instant;
+
+use dscale::{global::anykv, *};
+use examples::bandwidth::{Receiver, Sender};
+
+fn main() {
+    anykv::set::<usize>("messages_sent", 0);
+    anykv::set::<usize>("messages_received", 0);
+
+    let mut sim = SimulationBuilder::default()
+        .add_pool::<Sender>("Senders", 1)
+        .add_pool::<Receiver>("Receivers", 1)
+        .nic_bandwidth(BandwidthDescription::Bounded(1))
+        .latency_topology(&[LatencyDescription::BetweenPools(
+            "Senders",
+            "Receivers",
+            Distributions::Uniform(Jiffies(10), Jiffies(10)),
+        )])
+        .time_budget(Jiffies(1_000_000))
+        .seed(42)
+        .build();
+
+    let start = Instant::now();
+    sim.run();
+    let elapsed = start.elapsed();
+
+    println!(
+        "bandwidth {:>10} events in {:>8.2?}: {:>10.0} events/s",
+        sim.executed_events(),
+        elapsed,
+        sim.executed_events() as f64 / elapsed.as_secs_f64()
+    );
+}
@@ -0,0 +1,41 @@
+use std::fs::File;
+
+use dscale::*;
+use examples::pingpong::PingPongProcess;
+
+// Open the resulting trace.json in https://ui.perfetto.dev, paste the printed diagram into https://mermaid.live
+fn main() {
+    let mut sim = SimulationBuilder::default()
+        .add_pool::<PingPongProcess>("ExamplePool", 2)
+        .latency_topology(&[LatencyDescription::WithinPool(
+            "ExamplePool",
+            Distributions::Uniform(Jiffies(0), Jiffies(10)),
+        )])
+        .time_budget(Jiffies(1000))
+        .seed(5)
+        .record_trace()
+        .build();
+
+    global::anykv::set::<usize>("pings", 0);
+    global::anykv::set::<usize>("pongs", 0);
+
+    sim.run();
+
+    let trace = sim.take_trace();
+    trace
+        .write_chrome(File::create("trace.json").expect("Cannot create trace.json"))
+        .expect("Cannot write trace");
+
+    println!(
+        "Wrote {} handler invocations to trace.json",
+        trace.invocations.len()
+    );
+
+    println!(
+        "{}",
+        trace
+            .sequence_chart()
+            .window(Jiffies(0)..Jiffies(30))
+            .to_mermaid()
+    );
+}
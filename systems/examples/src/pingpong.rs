@@ -34,7 +34,6 @@ impl ProcessHandle for PingPongProcess {
             debug_process!("Sending Ping");
             anykv::modify::<usize>("pings", |p| *p += 1);
             send_to(2, PingPongMessage::Ping);
-            return;
         }
     }
 
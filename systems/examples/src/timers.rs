@@ -62,16 +62,16 @@ impl ProcessHandle for LazyPingPong {
         debug_process!("Timer {} fired", timer_id);
 
         // Check if this is the heartbeat timer
-        if let Some(heartbeat_id) = self.heartbeat_timer {
-            if timer_id == heartbeat_id {
-                debug_process!("Heartbeat timer fired");
-                anykv::modify::<usize>("heartbeats", |count| *count += 1);
+        if let Some(heartbeat_id) = self.heartbeat_timer
+            && timer_id == heartbeat_id
+        {
+            debug_process!("Heartbeat timer fired");
+            anykv::modify::<usize>("heartbeats", |count| *count += 1);
 
-                // Reschedule the heartbeat timer for continuous operation
-                let new_timer_id = schedule_timer_after(Jiffies(1000));
-                self.heartbeat_timer = Some(new_timer_id);
-                return;
-            }
+            // Reschedule the heartbeat timer for continuous operation
+            let new_timer_id = schedule_timer_after(Jiffies(1000));
+            self.heartbeat_timer = Some(new_timer_id);
+            return;
         }
 
         // This must be a delayed response timer
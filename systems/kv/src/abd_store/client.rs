@@ -1,10 +1,11 @@
 use dscale::{
-    global::{anykv, configuration},
+    global::anykv,
+    helpers::workload::{
+        Arrival, KeyDistribution, Mix, Operation, RequestId, Workload, WorkloadGenerator,
+    },
     *,
 };
 
-use rand::{Rng, SeedableRng, rngs::StdRng, seq::IndexedRandom};
-
 use crate::abd_store::types::{Key, Value};
 
 #[derive(Default, Clone)]
@@ -31,16 +32,30 @@ impl Message for ClientReq {}
 impl Message for ClientResponse {}
 
 pub struct Client {
-    rng: Option<StdRng>,
-    keypool: Vec<Key>,
+    generator: WorkloadGenerator,
+    current: Option<RequestId>,
     current_op: ExecutionHistoryEntry,
 }
 
 impl Default for Client {
     fn default() -> Self {
+        // One operation at a time, 50/50 get/put over 5 keys
+        let workload = Workload::new(
+            Arrival::Closed {
+                concurrency: 1,
+                think_time: Jiffies(100),
+            },
+            Mix {
+                read: 0.5,
+                update: 0.5,
+                ..Mix::default()
+            },
+            KeyDistribution::Uniform,
+            5,
+        );
         Self {
-            rng: None,
-            keypool: vec![1, 3, 4, 6, 10],
+            generator: WorkloadGenerator::new(workload),
+            current: None,
             current_op: ExecutionHistoryEntry::default(),
         }
     }
@@ -48,8 +63,7 @@ impl Default for Client {
 
 impl ProcessHandle for Client {
     fn start(&mut self) {
-        self.rng = Some(StdRng::seed_from_u64(configuration::seed()));
-        schedule_timer_after(Jiffies(100));
+        self.generator.start();
     }
 
     fn on_message(&mut self, from: dscale::ProcessId, message: dscale::MessagePtr) {
@@ -71,48 +85,47 @@ impl ProcessHandle for Client {
             h.push(self.current_op.clone());
         });
 
-        schedule_timer_after(Jiffies(100));
+        if let Some(request) = self.current.take() {
+            self.generator.complete(request);
+        }
     }
 
-    fn on_timer(&mut self, _id: dscale::TimerId) {
-        self.do_random_operation();
+    fn on_timer(&mut self, id: dscale::TimerId) {
+        if let Some((request, operation)) = self.generator.on_timer(id) {
+            self.current = Some(request);
+            self.do_operation(operation);
+        }
     }
 }
 
 impl Client {
-    fn choose_key(&mut self) -> Key {
-        self.keypool
-            .choose(self.rng.as_mut().unwrap())
-            .copied()
-            .unwrap()
-    }
-
     fn choose_value(&self) -> Value {
         global_unique_id() // Make values monotonous
     }
 
-    fn choose_operation(&mut self) -> ClientReq {
-        let random_bool = self.rng.as_mut().unwrap().random::<bool>();
-        let random_key = self.choose_key();
-
+    fn choose_operation(&mut self, operation: Operation) -> ClientReq {
         self.current_op.start = now();
 
-        if random_bool {
-            debug_process!("Choosed operation: Get({random_key})");
-            self.current_op.operation = String::from(format!("Get({random_key})"));
-            ClientReq::GetRequest(random_key)
-        } else {
-            let value = self.choose_value();
-            debug_process!("Choosed operation: Put({random_key},{value})");
-            self.current_op.operation = String::from(format!("Put({random_key},{value})"));
-            ClientReq::PutRequest(random_key, value)
+        match operation {
+            Operation::Read(key) => {
+                debug_process!("Choosed operation: Get({key})");
+                self.current_op.operation = format!("Get({key})");
+                ClientReq::GetRequest(key)
+            }
+            Operation::Update(key) => {
+                let value = self.choose_value();
+                debug_process!("Choosed operation: Put({key},{value})");
+                self.current_op.operation = format!("Put({key},{value})");
+                ClientReq::PutRequest(key, value)
+            }
+            _ => unreachable!("Workload issues gets and puts only"),
         }
     }
 
-    fn do_random_operation(&mut self) {
+    fn do_operation(&mut self, operation: Operation) {
         let target = choose_from_pool("Replicas");
-        let operation = self.choose_operation();
-        send_to(target, operation);
+        let request = self.choose_operation(operation);
+        send_to(target, request);
         debug_process!("Sent operation to {target}");
     }
 }
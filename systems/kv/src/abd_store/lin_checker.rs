@@ -2,6 +2,8 @@ use crate::abd_store::client::ExecutionHistory;
 use crate::abd_store::types::{Key, Value};
 use std::collections::{HashMap, HashSet};
 
+use log::debug;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Operation {
     Read(Value),
@@ -57,7 +59,7 @@ pub fn check_linearizable(history: &ExecutionHistory) -> bool {
         }
     }
 
-    println!("Checker: History is linearizable!");
+    debug!("Checker: History is linearizable!");
     true
 }
 
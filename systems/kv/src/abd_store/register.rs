@@ -14,6 +14,7 @@ pub(crate) struct RoutedRegisterOp {
     pub(crate) op: RegisterOps,
 }
 
+#[allow(clippy::enum_variant_names)]
 pub(crate) enum RegisterOps {
     RegisterReadRequest(ReadSequence),
     RegisterReadResponse(Value, Timestamp, ReadSequence),
@@ -85,7 +86,6 @@ impl MWMRAtomicRegister {
                 op: RegisterOps::RegisterReadRequest(self.r),
             },
         );
-        return;
     }
 
     pub(crate) fn read(&mut self, client: ClientId) {
@@ -123,7 +123,6 @@ impl MWMRAtomicRegister {
                         op: RegisterOps::RegisterReadResponse(self.local_value, self.local_ts, r_),
                     },
                 );
-                return;
             }
 
             RegisterOps::RegisterWriteRequest(v_, t_) => {
@@ -138,7 +137,6 @@ impl MWMRAtomicRegister {
                         op: RegisterOps::RegisterWriteAck(v_, t_),
                     },
                 );
-                return;
             }
 
             RegisterOps::RegisterReadResponse(v_, t_, r) => {
@@ -182,7 +180,7 @@ impl MWMRAtomicRegister {
                             let (v_m, t_m, _) = qourum_info
                                 .read_quorum
                                 .iter()
-                                .max_by(|l, r| ((l.1, l.0)).cmp(&(r.1, r.0)))
+                                .max_by(|l, r| (l.1, l.0).cmp(&(r.1, r.0)))
                                 .copied()
                                 .unwrap();
 
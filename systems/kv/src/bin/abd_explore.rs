@@ -0,0 +1,42 @@
+use std::time::Duration;
+
+use dscale::{explore::Explorer, global::anykv, *};
+use kv::abd_store::{
+    Replica,
+    client::{Client, ExecutionHistory},
+    lin_checker::check_linearizable,
+    types::{CLIENT_POOL_NAME, REPLICA_POOL_NAME},
+};
+
+// Explores delivery orders of 3 replicas and 2 clients, checking linearizability on every state.
+// Clients record operation times and schedule timers, so sleep sets would be unsound here.
+fn main() {
+    let report = Explorer::new(|| {
+        let sim = SimulationBuilder::default()
+            .add_pool::<Replica>(REPLICA_POOL_NAME, 3)
+            .add_pool::<Client>(CLIENT_POOL_NAME, 2)
+            .build();
+        anykv::set::<ExecutionHistory>("linearizable_history", ExecutionHistory::new());
+        sim
+    })
+    .invariant("linearizable", || {
+        check_linearizable(&anykv::get::<ExecutionHistory>("linearizable_history"))
+    })
+    .partial_order_reduction(false)
+    .max_depth(40)
+    .wall_clock_budget(Duration::from_secs(20))
+    .run();
+
+    println!(
+        "{} executions, {} transitions, complete: {}, elapsed: {:?}",
+        report.executions, report.transitions, report.complete, report.elapsed
+    );
+
+    if let Some(violation) = report.violation {
+        println!("{:?} after:", violation.kind);
+        violation
+            .trace
+            .iter()
+            .for_each(|transition| println!("  {transition:?}"));
+    }
+}
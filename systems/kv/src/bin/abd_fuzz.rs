@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use dscale::{
+    fuzz::{FuzzCampaign, FuzzCase},
+    global::anykv,
+    *,
+};
+use kv::abd_store::{
+    Replica,
+    client::{Client, ExecutionHistory},
+    lin_checker::check_linearizable,
+    types::{CLIENT_POOL_NAME, REPLICA_POOL_NAME},
+};
+
+// Panics are caught only with unwinding, so run without --release (panic = "abort" there)
+fn main() {
+    let report = FuzzCampaign::new(|case: &FuzzCase| {
+        let sim = SimulationBuilder::default()
+            .add_pool::<Replica>(REPLICA_POOL_NAME, case.processes)
+            .add_pool::<Client>(CLIENT_POOL_NAME, 4)
+            .time_budget(case.time_budget)
+            .latency_topology(&[
+                LatencyDescription::WithinPool(
+                    REPLICA_POOL_NAME,
+                    Distributions::Uniform(Jiffies(0), Jiffies(10)),
+                ),
+                LatencyDescription::WithinPool(
+                    CLIENT_POOL_NAME,
+                    Distributions::Uniform(Jiffies(0), Jiffies(545)),
+                ),
+                LatencyDescription::BetweenPools(
+                    CLIENT_POOL_NAME,
+                    REPLICA_POOL_NAME,
+                    Distributions::Uniform(Jiffies(0), Jiffies(1212)),
+                ),
+            ])
+            .seed(case.seed)
+            .build();
+
+        anykv::set::<ExecutionHistory>("linearizable_history", ExecutionHistory::new());
+        sim
+    })
+    .check(|| check_linearizable(&anykv::get::<ExecutionHistory>("linearizable_history")))
+    .seeds(0..10_000)
+    .processes(10)
+    .min_processes(3)
+    .time_budget(Jiffies(5000))
+    .wall_clock_budget(Duration::from_secs(60))
+    .run();
+
+    println!(
+        "Ran {} seeds in {:?}, {} failures",
+        report.runs,
+        report.elapsed,
+        report.failures.len()
+    );
+
+    for failure in report.failures {
+        println!(
+            "seed {} | replicas {} | budget {} | {:?} at {}",
+            failure.case.seed,
+            failure.case.processes,
+            failure.case.time_budget,
+            failure.kind,
+            failure.at
+        );
+    }
+}
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use dscale::{global::anykv, *};
 use kv::abd_store::{
     Replica,
@@ -7,8 +9,8 @@ use kv::abd_store::{
 };
 
 fn main() {
-    // 1 jiffy == 1ms
     let mut sim = SimulationBuilder::default()
+        .jiffy(Duration::from_millis(1))
         .add_pool::<Replica>(REPLICA_POOL_NAME, 10)
         .add_pool::<Client>(CLIENT_POOL_NAME, 4)
         .time_budget(Jiffies(5000))
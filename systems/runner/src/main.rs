@@ -0,0 +1,110 @@
+// Runs a declarative scenario file with the processes of the systems in this
+// workspace, see dscale::scenario for the format.
+//
+// Usage: dscale <scenario.toml> [--seed N]
+//        dscale --list
+
+use std::{env, process};
+
+use dag_based::{bullshark::Bullshark, rider::DAGRider, sparse_bullshark::SparseBullshark};
+use dscale::{
+    global::anykv,
+    scenario::{Registry, Scenario},
+};
+use kv::abd_store::{
+    Replica,
+    client::{Client, ExecutionHistory},
+    lin_checker::check_linearizable,
+};
+
+const USAGE: &str = "Usage: dscale <scenario.toml> [--seed N]\n       dscale --list";
+
+fn registry() -> Registry {
+    let mut registry = Registry::new();
+
+    // (avg_latency, total_vertex)
+    let setup_latency = || anykv::set::<(f64, usize)>("avg_latency", (0.0, 0));
+    let report_latency = || {
+        let (avg_latency, ordered) = anykv::get::<(f64, usize)>("avg_latency");
+        format!("ordered vertices: {ordered}\naverage latency: {avg_latency:.2} jiffies")
+    };
+
+    registry
+        .register::<Bullshark>("bullshark")
+        .setup(setup_latency)
+        .report(report_latency);
+    registry
+        .register::<DAGRider>("rider")
+        .setup(setup_latency)
+        .report(report_latency);
+    // Needs the sample size `D` in the parameters
+    registry
+        .register::<SparseBullshark>("sparse_bullshark")
+        .setup(move || {
+            setup_latency();
+            anykv::set::<(f64, usize)>("avg_virtual_size", (0.0, 0));
+        })
+        .report(move || {
+            let (avg_virtual_size, _) = anykv::get::<(f64, usize)>("avg_virtual_size");
+            format!(
+                "{}\naverage message size: {avg_virtual_size:.2} bytes",
+                report_latency()
+            )
+        });
+
+    // Pools should be named "Replicas" and "Clients"
+    registry.register::<Replica>("abd_replica");
+    registry
+        .register::<Client>("abd_client")
+        .setup(|| anykv::set::<ExecutionHistory>("linearizable_history", ExecutionHistory::new()))
+        .report(|| {
+            let history = anykv::get::<ExecutionHistory>("linearizable_history");
+            format!(
+                "operations: {}\nlinearizable: {}",
+                history.len(),
+                check_linearizable(&history)
+            )
+        });
+
+    registry
+}
+
+fn fail(message: impl std::fmt::Display) -> ! {
+    eprintln!("{message}");
+    process::exit(1);
+}
+
+fn main() {
+    let registry = registry();
+
+    let mut path = None;
+    let mut seed = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--list" => {
+                registry.names().for_each(|name| println!("{name}"));
+                return;
+            }
+            "--seed" => {
+                let value = args.next().unwrap_or_else(|| fail(USAGE));
+                seed = Some(value.parse().unwrap_or_else(|_| fail(USAGE)));
+            }
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return;
+            }
+            _ if path.is_none() => path = Some(arg),
+            _ => fail(USAGE),
+        }
+    }
+    let path = path.unwrap_or_else(|| fail(USAGE));
+
+    let mut scenario = Scenario::load(&path).unwrap_or_else(|error| fail(error));
+    if let Some(seed) = seed {
+        scenario.seed = seed;
+    }
+
+    let report = scenario.run(&registry).unwrap_or_else(|error| fail(error));
+    print!("{report}");
+}